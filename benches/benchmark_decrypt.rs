@@ -22,10 +22,10 @@ fn bench_decrypt_string(c: &mut Criterion) {
     let sk_string = keypair.get("secret").unwrap();
     let pk_string = keypair.get("public").unwrap();
     let message = String::from("hello");
-    let ciphertext_string = encrypt_string(&pk_string, &message, &params, None);
+    let ciphertext_string = encrypt_string(pk_string, &message, &params, None).unwrap();
     
     c.bench_function("decrypt_string", |b| {
-        b.iter(|| decrypt_string(&sk_string, &ciphertext_string, &params))
+        b.iter(|| decrypt_string(sk_string, &ciphertext_string, &params))
     });
 }
 
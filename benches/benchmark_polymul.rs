@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use ring_lwe::utils::{polymul, polymul_fast, Parameters, gen_uniform_poly};
+use ring_lwe::utils::{polymul, polymul_fast, polymul_negacyclic, Parameters, gen_uniform_poly};
 use ntt::omega;
 use polynomial_ring::Polynomial;
 
@@ -42,6 +42,11 @@ fn benchmark_polymul_uniform(c: &mut Criterion) {
     c.bench_function("Fast polymul (large)", |b| {
         b.iter(|| polymul_fast(&poly_0, &poly_1, q, &params.f, omega))
     });
+
+    // Time negacyclic multiplication
+    c.bench_function("Negacyclic polymul (large)", |b| {
+        b.iter(|| polymul_negacyclic(&poly_0, &poly_1, q, &params.f, omega))
+    });
 }
 
 criterion_group!(benches, benchmark_polymul_small, benchmark_polymul_uniform);
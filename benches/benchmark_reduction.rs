@@ -0,0 +1,62 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ring_lwe::reduction::{Barrett16, Barrett32, Montgomery16, Montgomery32};
+use ring_lwe::utils::{mod_coeffs, polyadd, Parameters, gen_uniform_poly};
+
+fn benchmark_reduction_products(c: &mut Criterion) {
+    let seed = None; // Set the random seed
+    let n = 1024;
+
+    for q in [3329u16, 12289] {
+        // Products of two residues, as produced by the NTT butterflies
+        let a = gen_uniform_poly(n, q as i64, seed);
+        let b = gen_uniform_poly(n, q as i64, seed);
+        let products: Vec<u32> = a.coeffs().iter().zip(b.coeffs())
+            .map(|(x, y)| (x.rem_euclid(q as i64) * y.rem_euclid(q as i64)) as u32)
+            .collect();
+        let (mont16, barrett16) = (Montgomery16::new(q), Barrett16::new(q));
+        let (mont32, barrett32) = (Montgomery32::new(q as u32), Barrett32::new(q as u32));
+
+        // Time the remainder operator used before
+        c.bench_function(&format!("rem_euclid (q={})", q), |bench| {
+            bench.iter(|| black_box(&products).iter().map(|&p| (p as i64).rem_euclid(q as i64)).sum::<i64>())
+        });
+
+        // Time Barrett reduction
+        c.bench_function(&format!("Barrett16 (q={})", q), |bench| {
+            bench.iter(|| black_box(&products).iter().map(|&p| barrett16.reduce(p) as i64).sum::<i64>())
+        });
+        c.bench_function(&format!("Barrett32 (q={})", q), |bench| {
+            bench.iter(|| black_box(&products).iter().map(|&p| barrett32.reduce(p as u64) as i64).sum::<i64>())
+        });
+
+        // Time Montgomery reduction
+        c.bench_function(&format!("Montgomery16 (q={})", q), |bench| {
+            bench.iter(|| black_box(&products).iter().map(|&p| mont16.reduce(p) as i64).sum::<i64>())
+        });
+        c.bench_function(&format!("Montgomery32 (q={})", q), |bench| {
+            bench.iter(|| black_box(&products).iter().map(|&p| mont32.reduce(p as u64) as i64).sum::<i64>())
+        });
+    }
+}
+
+fn benchmark_reduction_polyadd(c: &mut Criterion) {
+    let seed = None; // Set the random seed
+    let params = Parameters::default();
+    let (n, q, f) = (params.n, params.q, &params.f);
+
+    let poly_0 = gen_uniform_poly(n, q, seed);
+    let poly_1 = gen_uniform_poly(n, q, seed);
+
+    // Time addition with the rem_euclid reduction of mod_coeffs
+    c.bench_function("polyadd with mod_coeffs", |b| {
+        b.iter(|| mod_coeffs(&poly_0 + &poly_1, q))
+    });
+
+    // Time addition with Barrett reduction
+    c.bench_function("polyadd with Barrett", |b| {
+        b.iter(|| polyadd(&poly_0, &poly_1, q, f))
+    });
+}
+
+criterion_group!(benches, benchmark_reduction_products, benchmark_reduction_polyadd);
+criterion_main!(benches);
@@ -0,0 +1,82 @@
+use crate::utils::{Parameters, pad_coeffs, pack_coeffs};
+use crate::error::RingLweError;
+use polynomial_ring::Polynomial;
+
+/// Number of bytes in the little-endian length header in front of every message
+pub const LENGTH_HEADER_BYTES: usize = 8;
+
+/// Number of message bits carried by each plaintext coefficient, floor(log2 t)
+/// # Arguments:
+/// * `t` - plaintext modulus, at least 2
+/// # Example:
+/// ```
+/// assert_eq!(ring_lwe::codec::bits_per_coeff(2), 1);
+/// assert_eq!(ring_lwe::codec::bits_per_coeff(17), 4);
+/// ```
+pub fn bits_per_coeff(t: i64) -> u32 {
+    63 - t.leading_zeros()
+}
+
+/// Encode a byte message as plaintext polynomials
+///
+/// The message is prefixed with its length as a little-endian u64, and the
+/// resulting bytes are split LSB first into floor(log2 t)-bit coefficients,
+/// n coefficients per polynomial.
+/// # Arguments:
+/// * `message` - bytes to encode
+/// * `params` - ring-LWE parameters
+/// # Returns:
+/// plaintext polynomials with coefficients in [0, t)
+/// # Example:
+/// ```
+/// let params = ring_lwe::utils::Parameters::default();
+/// let blocks = ring_lwe::codec::encode_message("héllo\0".as_bytes(), &params);
+/// assert_eq!(ring_lwe::codec::decode_message(&blocks, &params).unwrap(), "héllo\0".as_bytes());
+/// ```
+pub fn encode_message(message: &[u8], params: &Parameters) -> Vec<Polynomial<i64>> {
+    let bits = bits_per_coeff(params.t) as usize;
+    let mut data = (message.len() as u64).to_le_bytes().to_vec();
+    data.extend_from_slice(message);
+
+    // read the data as a stream of bits, zero past the end
+    let bit_at = |i: usize| data.get(i / 8).map_or(0, |byte| ((byte >> (i % 8)) & 1) as i64);
+    let coeffs: Vec<i64> = (0..(data.len() * 8).div_ceil(bits))
+        .map(|i| (0..bits).map(|j| bit_at(i * bits + j) << j).sum())
+        .collect();
+
+    coeffs
+        .chunks(params.n)
+        .map(|chunk| Polynomial::new(chunk.to_vec()))
+        .collect()
+}
+
+/// Decode plaintext polynomials written by `encode_message`
+/// # Arguments:
+/// * `blocks` - decrypted plaintext polynomials, in order
+/// * `params` - ring-LWE parameters
+/// # Returns:
+/// the original bytes, or an error if a coefficient does not fit in floor(log2 t) bits
+/// or the blocks are shorter than the length header says
+pub fn decode_message(blocks: &[Polynomial<i64>], params: &Parameters) -> Result<Vec<u8>, RingLweError> {
+    let bits = bits_per_coeff(params.t);
+    let limit = 1i64 << bits;
+    let coeffs: Vec<i64> = blocks.iter().flat_map(|block| pad_coeffs(block, params.n)).collect();
+    if let Some((index, &value)) = coeffs.iter().enumerate().find(|(_, &c)| !(0..limit).contains(&c)) {
+        return Err(RingLweError::InvalidCoefficient { index, value, modulus: limit });
+    }
+
+    // the coefficients are bit fields in the same LSB-first order that pack_coeffs writes
+    let data = pack_coeffs(&coeffs, limit, bits);
+    if data.len() < LENGTH_HEADER_BYTES {
+        return Err(RingLweError::LengthMismatch { expected: LENGTH_HEADER_BYTES, found: data.len() });
+    }
+    let (header, body) = data.split_at(LENGTH_HEADER_BYTES);
+    let len = u64::from_le_bytes(header.try_into().expect("header is 8 bytes"));
+    match usize::try_from(len) {
+        Ok(len) if len <= body.len() => Ok(body[..len].to_vec()),
+        _ => Err(RingLweError::LengthMismatch {
+            expected: usize::try_from(len).unwrap_or(usize::MAX).saturating_add(LENGTH_HEADER_BYTES),
+            found: data.len(),
+        }),
+    }
+}
@@ -1,13 +1,107 @@
-use crate::utils::{Parameters, polymul_fast, polyadd, nearest_int, decompress};
+use crate::utils::{Parameters, NttPoly, RingPoly, CoeffWord, WireFormat, nearest_int, deserialize_coeffs};
+use crate::types::{SecretKey, Ciphertext, CompressedCiphertext};
+use crate::error::RingLweError;
+use crate::format::{ObjectType, open};
+use crate::codec::decode_message;
 use polynomial_ring::Polynomial;
 
+impl<W: CoeffWord> SecretKey<W> {
+    /// Decrypt a ciphertext using the secret key
+    /// # Arguments:
+    /// * `ct` - ciphertext
+    /// # Returns:
+    /// decrypted polynomial, or an error if the ciphertext uses different parameters
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let m = polynomial_ring::Polynomial::new(vec![1, 0, 1]);
+    /// let ct = pk.encrypt(&m, None);
+    /// assert_eq!(sk.decrypt(&ct).unwrap(), m);
+    /// ```
+    pub fn decrypt(&self, ct: &Ciphertext<W>) -> Result<Polynomial<i64>, RingLweError> {
+        self.params().check_match(ct.params())?;
+        Ok(decrypt_poly(self.s_ntt(), ct.c0(), ct.c1(), self.params()))
+    }
+
+    /// Measure the remaining noise budget of a ciphertext
+    /// # Arguments:
+    /// * `ct` - ciphertext
+    /// # Returns:
+    /// bits of headroom before decryption fails, or an error if the ciphertext uses different parameters
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let ct = pk.encrypt(&polynomial_ring::Polynomial::new(vec![1, 0, 1]), None);
+    /// assert!(sk.noise_budget(&ct).unwrap() > 3.0);
+    /// ```
+    pub fn noise_budget(&self, ct: &Ciphertext<W>) -> Result<f64, RingLweError> {
+        self.params().check_match(ct.params())?;
+        Ok(noise_budget_poly(self.s_ntt(), ct.c0(), ct.c1(), self.params()))
+    }
+}
+
+/// Compute round(t/q * (c0 + c1*sk)) mod t
+fn decrypt_poly<W: CoeffWord>(sk_ntt: &NttPoly, c0: &RingPoly<W>, c1: &RingPoly<W>, params: &Parameters) -> Polynomial<i64> {
+    let (q,t) = (params.q, params.t);
+    let scaled_pt = c0 + &c1.mul_ntt(sk_ntt, &params.ntt());
+    let mut decrypted_coeffs = vec![];
+    let mut s;
+    for c in scaled_pt.to_centered().iter() {
+        s = nearest_int(c*t,q);
+        decrypted_coeffs.push(s.rem_euclid(t));
+    }
+    Polynomial::new(decrypted_coeffs)
+}
+
+/// Compute log2(q/(2t)) - log2(max |v|) for c0 + c1*sk = floor(m*q/t) + v, at least 0
+fn noise_budget_poly<W: CoeffWord>(sk_ntt: &NttPoly, c0: &RingPoly<W>, c1: &RingPoly<W>, params: &Parameters) -> f64 {
+    let (q,t) = (params.q, params.t);
+    let scaled_pt = c0 + &c1.mul_ntt(sk_ntt, &params.ntt());
+    let noise = scaled_pt.to_centered().iter().map(|&c| {
+        let m = nearest_int(c*t,q).rem_euclid(t);
+        let v = (c - m*q/t).rem_euclid(q);
+        v.min(q - v)
+    }).max().unwrap_or(0);
+    params.headroom_bits(noise as f64)
+}
+
+/// Measure the remaining noise budget of a ciphertext using the secret key
+///
+/// Decryption rounds away noise below q/(2t), so a budget of b bits means the
+/// noise can still grow by a factor of about 2^b.
+/// # Arguments:
+/// * `sk` - secret key
+/// * `ct` - array of ciphertext polynomials
+/// * `params` - ring-LWE parameters
+/// # Returns:
+/// bits of headroom, 0 once the ciphertext may decrypt wrongly
+/// # Example:
+/// ```
+/// let params = ring_lwe::utils::Parameters::default();
+/// let (pk, sk) = ring_lwe::keygen::keygen(&params, None);
+/// let ct = ring_lwe::encrypt::encrypt(&pk, &polynomial_ring::Polynomial::new(vec![1]), &params, None);
+/// let budget = ring_lwe::decrypt::noise_budget(&sk, &ct, &params);
+/// assert!(budget > 3.0 && budget < 11.0);
+/// ```
+pub fn noise_budget(
+    sk: &Polynomial<i64>,
+    ct: &[Polynomial<i64>; 2],
+    params: &Parameters
+) -> f64 {
+    let (n, q) = (params.n, params.q);
+    let to_ring = |x: &Polynomial<i64>| RingPoly::<u32>::from_poly(x, n, q);
+    noise_budget_poly(&to_ring(sk).to_ntt(&params.ntt()), &to_ring(&ct[0]), &to_ring(&ct[1]), params)
+}
+
 /// Decrypt a ciphertext using the secret key
 /// # Arguments:
 /// * `sk` - secret key
 /// * `ct` - array of ciphertext polynomials
 /// * `params` - ring-LWE parameters
 /// # Returns:
-///	decrypted polynomial
+/// decrypted polynomial
 /// # Example:
 /// ```
 /// let params = ring_lwe::utils::Parameters::default();
@@ -21,15 +115,9 @@ pub fn decrypt(
     ct: &[Polynomial<i64>; 2],        // Array of ciphertext polynomials
     params: &Parameters
 ) -> Polynomial<i64> {
-    let (_n,q,t,f,omega) = (params.n, params.q, params.t, &params.f, params.omega);
-	let scaled_pt = polyadd(&polymul_fast(&ct[1], sk, q, f, omega),&ct[0], q, f);
-	let mut decrypted_coeffs = vec![];
-	let mut s;
-	for c in scaled_pt.coeffs().iter() {
-		s = nearest_int(c*t,q);
-		decrypted_coeffs.push(s.rem_euclid(t));
-	}
-    Polynomial::new(decrypted_coeffs)
+    let (n, q) = (params.n, params.q);
+    let to_ring = |x: &Polynomial<i64>| RingPoly::<u32>::from_poly(x, n, q);
+    decrypt_poly(&to_ring(sk).to_ntt(&params.ntt()), &to_ring(&ct[0]), &to_ring(&ct[1]), params)
 }
 
 /// Decrypt a ciphertext string using the secret key
@@ -38,7 +126,7 @@ pub fn decrypt(
 /// * `ciphertext_string` - ciphertext to decrypt as a base64 encoded string
 /// * `params` - ring-LWE parameters
 /// # Returns:
-///	decrypted plaintext message
+/// decrypted plaintext message, or an error if the key or ciphertext is malformed
 /// # Example:
 /// ```
 /// let params = ring_lwe::utils::Parameters::default();
@@ -46,36 +134,97 @@ pub fn decrypt(
 /// let sk_string = keys.get("secret").unwrap();
 /// let pk_string = keys.get("public").unwrap();
 /// let message = String::from("hello");
-/// let ciphertext_string = ring_lwe::encrypt::encrypt_string(pk_string, &message, &params, None);
-/// let decrypted_message = ring_lwe::decrypt::decrypt_string(sk_string, &ciphertext_string, &params);
+/// let ciphertext_string = ring_lwe::encrypt::encrypt_string(pk_string, &message, &params, None).unwrap();
+/// let decrypted_message = ring_lwe::decrypt::decrypt_string(sk_string, &ciphertext_string, &params).unwrap();
+/// ```
+pub fn decrypt_string(sk_base64: &str, ciphertext_base64: &str, params: &Parameters) -> Result<String, RingLweError> {
+    decrypt_string_with_format(sk_base64, ciphertext_base64, params, WireFormat::Bincode)
+}
+
+/// Decrypt a ciphertext string using the secret key, with the key and ciphertext in the given wire format
+/// # Arguments:
+/// * `sk_string` - secret key as a base64 encoded string
+/// * `ciphertext_string` - ciphertext to decrypt as a base64 encoded string
+/// * `params` - ring-LWE parameters
+/// * `format` - wire format of the secret key and the ciphertext
+/// # Returns:
+/// decrypted plaintext message, or an error if the key or ciphertext is malformed
+/// # Example:
+/// ```
+/// use ring_lwe::utils::WireFormat;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let keys = ring_lwe::keygen::keygen_string_with_format(&params, None, WireFormat::Packed);
+/// let ciphertext_string = ring_lwe::encrypt::encrypt_string_with_format(&keys["public"], "hello", &params, None, WireFormat::Packed).unwrap();
+/// let message = ring_lwe::decrypt::decrypt_string_with_format(&keys["secret"], &ciphertext_string, &params, WireFormat::Packed).unwrap();
+/// assert_eq!(message, "hello");
 /// ```
-pub fn decrypt_string(sk_base64: &String, ciphertext_base64: &String, params: &Parameters) -> String {
-    // Decode the base64 secret key string and deserialize into a vector of i64 coefficients
-    let sk = Polynomial::new(decompress(sk_base64));
+pub fn decrypt_string_with_format(sk_base64: &str, ciphertext_base64: &str, params: &Parameters, format: WireFormat) -> Result<String, RingLweError> {
+    let message = decrypt_bytes(sk_base64, ciphertext_base64, params, format)?;
+    String::from_utf8(message).map_err(|err| RingLweError::Decode(format!("message is not valid UTF-8: {}", err)))
+}
 
-    // Decode the Base64 ciphertext string and deserialize into vector of i64 coefficients
-    let ciphertext_array: Vec<i64> = decompress(ciphertext_base64);
+/// Decrypt a ciphertext string to the bytes it encrypts
+/// # Arguments:
+/// * `sk_string` - secret key as a base64 encoded string
+/// * `ciphertext_string` - ciphertext to decrypt as a base64 encoded string
+/// * `params` - ring-LWE parameters
+/// * `format` - wire format of the secret key and the ciphertext
+/// # Returns:
+/// decrypted bytes, or an error if the key or ciphertext is malformed
+/// # Example:
+/// ```
+/// use ring_lwe::utils::WireFormat;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let keys = ring_lwe::keygen::keygen_string(&params, None);
+/// let ciphertext_string = ring_lwe::encrypt::encrypt_bytes(&keys["public"], &[0, 159, 146, 150, 0], &params, None, WireFormat::Bincode).unwrap();
+/// let message = ring_lwe::decrypt::decrypt_bytes(&keys["secret"], &ciphertext_string, &params, WireFormat::Bincode).unwrap();
+/// assert_eq!(message, vec![0, 159, 146, 150, 0]);
+/// ```
+pub fn decrypt_bytes(sk_base64: &str, ciphertext_base64: &str, params: &Parameters, format: WireFormat) -> Result<Vec<u8>, RingLweError> {
+    // Decode the base64 secret key string
+    let sk = SecretKey::decode(sk_base64, params, format)?;
 
-    let num_blocks = ciphertext_array.len() / (2 * params.n);
-    let mut decrypted_bits: Vec<i64> = Vec::new();
+    // Decode the Base64 ciphertext string into (c0, c1) blocks
+    let ciphertexts = match open(ciphertext_base64, ObjectType::Ciphertext, params, format)? {
+        (WireFormat::Bincode, _, payload) => {
+            let ciphertext_array: Vec<i64> = deserialize_coeffs(&payload)?;
+            split_blocks(&ciphertext_array, 2 * params.n)?
+                .into_iter()
+                .map(|block| Ciphertext::from_coeffs(block, params))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+        (WireFormat::Packed, version, ciphertext_bytes) => {
+            // blocks written before container version 2 hold only the packed coefficients
+            let block_len = if version >= 2 { Ciphertext::packed_len(params) } else { Ciphertext::packed_coeffs_len(params) };
+            split_blocks(&ciphertext_bytes, block_len)?
+                .into_iter()
+                .map(|block| Ciphertext::from_packed(block, params))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
 
-    for i in 0..num_blocks {
-        let c0 = Polynomial::new(ciphertext_array[2 * i * params.n..(2 * i + 1) * params.n].to_vec());
-        let c1 = Polynomial::new(ciphertext_array[(2 * i + 1) * params.n..(2 * i + 2) * params.n].to_vec());
-        let ct = [c0, c1];
+    // Decrypt each block and decode the length-prefixed message
+    let message_blocks = ciphertexts
+        .iter()
+        .map(|ct| sk.decrypt(ct))
+        .collect::<Result<Vec<_>, _>>()?;
+    decode_message(&message_blocks, params)
+}
 
-        // Decrypt the ciphertext
-        decrypted_bits.extend(decrypt(&sk, &ct, &params).coeffs());
+/// Split serialized ciphertext blocks, which must all be whole
+fn split_blocks<T>(data: &[T], block_len: usize) -> Result<Vec<&[T]>, RingLweError> {
+    if !data.len().is_multiple_of(block_len) {
+        let expected = data.len().div_ceil(block_len) * block_len;
+        return Err(RingLweError::LengthMismatch { expected, found: data.len() });
     }
+    Ok(data.chunks(block_len).collect())
+}
 
-    // Convert decrypted bits into a string
-    let decrypted_message: String = decrypted_bits
-        .chunks(8)
-        .map(|byte| {
-            let bit_str: String = byte.iter().map(|&b| (b as u8 + b'0') as char).collect();
-            u8::from_str_radix(&bit_str, 2).unwrap_or(0) as char
-        })
-        .collect();
-
-    decrypted_message.trim_end_matches('\0').to_string()
+/// Lift a compressed ciphertext back to a ciphertext that `SecretKey::decrypt` accepts
+/// # Arguments:
+/// * `ct` - ciphertext compressed with `encrypt::compress_ciphertext`
+/// # Returns:
+/// ciphertext with each coefficient within about q / 2^(d+1) of the original
+pub fn decompress_ciphertext(ct: &CompressedCiphertext) -> Ciphertext {
+    ct.decompress()
 }
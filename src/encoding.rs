@@ -0,0 +1,279 @@
+use crate::utils::{Parameters, NttContext, RingPoly, is_prime, pad_coeffs};
+use std::collections::HashMap;
+use crate::error::RingLweError;
+use polynomial_ring::Polynomial;
+
+/// Centered representative of x mod t, in (-t/2, t/2]
+fn center(x: i64, t: i64) -> i64 {
+    let r = x.rem_euclid(t);
+    if r > t / 2 { r - t } else { r }
+}
+
+/// Encoder for a single integer as a polynomial of its base-b digits
+///
+/// The value is the polynomial evaluated at x = b, so sums and products of encodings
+/// encode sums and products of the values, as long as no coefficient wraps modulo t.
+/// Decoding evaluates the polynomial at b instead of reading off digits, which absorbs
+/// the carries left by homomorphic operations.
+/// # Example:
+/// ```
+/// use ring_lwe::encoding::IntegerEncoder;
+/// let params = ring_lwe::utils::Parameters::builder().t(17).build().unwrap();
+/// let encoder = IntegerEncoder::new(2, &params).unwrap();
+/// let (pk, sk) = ring_lwe::keygen::keygen(&params, None);
+/// let ct = ring_lwe::encrypt::encrypt(&pk, &encoder.encode(-42).unwrap(), &params, None);
+/// let decrypted = ring_lwe::decrypt::decrypt(&sk, &ct, &params);
+/// assert_eq!(encoder.decode(&decrypted).unwrap(), -42);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntegerEncoder {
+    base: i64,
+    n: usize,
+    t: i64,
+}
+
+impl IntegerEncoder {
+    /// Create an encoder for base-b digits
+    /// # Arguments:
+    /// * `base` - digit base b, with 2 <= b and 2(b-1) < t so that digits of either sign decode centered mod t
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// encoder, or an error if the base is out of range
+    pub fn new(base: i64, params: &Parameters) -> Result<Self, RingLweError> {
+        if base < 2 || 2 * (base - 1) >= params.t {
+            return Err(RingLweError::InvalidParameters(format!("base {} is not in [2, t/2 + 1) for t = {}", base, params.t)));
+        }
+        Ok(IntegerEncoder { base, n: params.n, t: params.t })
+    }
+
+    /// Digit base b
+    pub fn base(&self) -> i64 {
+        self.base
+    }
+
+    /// Encode an integer as its base-b digits, all carrying the sign of the value, stored mod t
+    /// # Arguments:
+    /// * `value` - integer to encode
+    /// # Returns:
+    /// polynomial with coefficients in [0, t), or an error if the value has more than n digits
+    pub fn encode(&self, value: i64) -> Result<Polynomial<i64>, RingLweError> {
+        let mut digits = vec![];
+        let mut rest = value.unsigned_abs();
+        while rest > 0 {
+            let digit = (rest % self.base as u64) as i64;
+            digits.push(if value < 0 { (-digit).rem_euclid(self.t) } else { digit });
+            rest /= self.base as u64;
+        }
+        if digits.len() > self.n {
+            return Err(RingLweError::LengthMismatch { expected: self.n, found: digits.len() });
+        }
+        Ok(Polynomial::new(digits))
+    }
+
+    /// Decode a polynomial by evaluating it at b, with coefficients taken as centered mod t
+    /// # Arguments:
+    /// * `poly` - plaintext polynomial, e.g. from `decrypt::decrypt`
+    /// # Returns:
+    /// encoded integer, or an error if it does not fit in an i64
+    pub fn decode(&self, poly: &Polynomial<i64>) -> Result<i64, RingLweError> {
+        let overflow = || RingLweError::Decode(String::from("encoded integer does not fit in an i64"));
+        // Horner's rule from the highest digit down
+        let mut value: i128 = 0;
+        for &c in poly.coeffs().iter().rev() {
+            value = value
+                .checked_mul(self.base as i128)
+                .and_then(|v| v.checked_add(center(c, self.t) as i128))
+                .filter(|v| v.unsigned_abs() <= 1 << 64)
+                .ok_or_else(overflow)?;
+        }
+        i64::try_from(value).map_err(|_| overflow())
+    }
+}
+
+/// Encoder for a vector of up to n values in [0, t), one per coefficient
+///
+/// Homomorphic addition acts coefficient-wise; multiplication is the negacyclic
+/// convolution of the two vectors.
+/// # Example:
+/// ```
+/// use ring_lwe::encoding::CoeffEncoder;
+/// let params = ring_lwe::utils::Parameters::builder().t(17).build().unwrap();
+/// let encoder = CoeffEncoder::new(&params);
+/// let (pk, sk) = ring_lwe::keygen::keygen(&params, None);
+/// let ct = ring_lwe::encrypt::encrypt(&pk, &encoder.encode(&[3, 0, 16]).unwrap(), &params, None);
+/// let decrypted = ring_lwe::decrypt::decrypt(&sk, &ct, &params);
+/// assert_eq!(encoder.decode(&decrypted)[..4], [3, 0, 16, 0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoeffEncoder {
+    n: usize,
+    t: i64,
+}
+
+impl CoeffEncoder {
+    /// Create an encoder for the plaintext space of the parameters
+    pub fn new(params: &Parameters) -> Self {
+        CoeffEncoder { n: params.n, t: params.t }
+    }
+
+    /// Encode values as the coefficients of a polynomial
+    /// # Arguments:
+    /// * `values` - at most n values in [0, t)
+    /// # Returns:
+    /// polynomial with the values as coefficients, or an error if there are too many values
+    /// or one is out of range
+    pub fn encode(&self, values: &[u64]) -> Result<Polynomial<i64>, RingLweError> {
+        if values.len() > self.n {
+            return Err(RingLweError::LengthMismatch { expected: self.n, found: values.len() });
+        }
+        if let Some((index, &value)) = values.iter().enumerate().find(|(_, &v)| v >= self.t as u64) {
+            return Err(RingLweError::InvalidCoefficient { index, value: value as i64, modulus: self.t });
+        }
+        Ok(Polynomial::new(values.iter().map(|&v| v as i64).collect()))
+    }
+
+    /// Decode a polynomial into its n coefficients reduced into [0, t)
+    pub fn decode(&self, poly: &Polynomial<i64>) -> Vec<u64> {
+        pad_coeffs(poly, self.n).iter().map(|c| c.rem_euclid(self.t) as u64).collect()
+    }
+}
+
+/// Encoder for a vector of up to n signed values, stored as centered representatives mod t
+/// # Example:
+/// ```
+/// use ring_lwe::encoding::SignedEncoder;
+/// let params = ring_lwe::utils::Parameters::builder().t(17).build().unwrap();
+/// let encoder = SignedEncoder::new(&params);
+/// let (pk, sk) = ring_lwe::keygen::keygen(&params, None);
+/// let ct = ring_lwe::encrypt::encrypt(&pk, &encoder.encode(&[-8, 0, 8]).unwrap(), &params, None);
+/// let decrypted = ring_lwe::decrypt::decrypt(&sk, &ct, &params);
+/// assert_eq!(encoder.decode(&decrypted)[..4], [-8, 0, 8, 0]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignedEncoder {
+    n: usize,
+    t: i64,
+}
+
+impl SignedEncoder {
+    /// Create an encoder for the plaintext space of the parameters
+    pub fn new(params: &Parameters) -> Self {
+        SignedEncoder { n: params.n, t: params.t }
+    }
+
+    /// Encode values as the coefficients of a polynomial, reduced into [0, t)
+    /// # Arguments:
+    /// * `values` - at most n values in (-t/2, t/2]
+    /// # Returns:
+    /// polynomial with the values mod t as coefficients, or an error if there are too many
+    /// values or one is out of range
+    pub fn encode(&self, values: &[i64]) -> Result<Polynomial<i64>, RingLweError> {
+        if values.len() > self.n {
+            return Err(RingLweError::LengthMismatch { expected: self.n, found: values.len() });
+        }
+        if let Some((index, &value)) = values.iter().enumerate().find(|(_, &v)| center(v, self.t) != v) {
+            return Err(RingLweError::InvalidCoefficient { index, value, modulus: self.t });
+        }
+        Ok(Polynomial::new(values.iter().map(|v| v.rem_euclid(self.t)).collect()))
+    }
+
+    /// Decode a polynomial into its n coefficients as centered representatives in (-t/2, t/2]
+    pub fn decode(&self, poly: &Polynomial<i64>) -> Vec<i64> {
+        pad_coeffs(poly, self.n).iter().map(|&c| center(c, self.t)).collect()
+    }
+}
+
+/// Encoder for n independent values mod t in the slots of one plaintext
+///
+/// When t is a prime with t = 1 mod 2n, x^n+1 splits into n linear factors x - z^e mod t,
+/// with z a primitive 2n-th root of unity and e odd, and a plaintext is determined by its
+/// n values p(z^e). Homomorphic addition and multiplication then act slot-wise.
+/// The slots form a 2 x n/2 matrix: row 0 holds p(z^(3^j)) and row 1 holds p(z^(-3^j)),
+/// so `evaluate::rotate_rows` rotates both rows left and `evaluate::rotate_columns` swaps them.
+/// # Example:
+/// ```
+/// use ring_lwe::encoding::BatchEncoder;
+/// let params = ring_lwe::utils::Parameters::builder().q(1073479681).t(12289).build().unwrap();
+/// let encoder = BatchEncoder::new(&params).unwrap();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let a = pk.encrypt(&encoder.encode(&[1, 2, 3]).unwrap(), None);
+/// let b = pk.encrypt(&encoder.encode(&[10, 20, 12288]).unwrap(), None);
+/// let sum = sk.decrypt(&ring_lwe::evaluate::add(&a, &b).unwrap()).unwrap();
+/// assert_eq!(encoder.decode(&sum)[..4], [11, 22, 2, 0]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchEncoder {
+    n: usize,
+    t: i64,
+    ntt: NttContext,  // negacyclic NTT mod t
+    slots: Vec<usize>, // index of the NTT evaluation held by each slot
+}
+
+impl BatchEncoder {
+    /// Create an encoder for the slots of the plaintext space of the parameters
+    /// # Arguments:
+    /// * `params` - ring-LWE parameters with t a prime and t = 1 mod 2n
+    /// # Returns:
+    /// encoder, or an error if x^n+1 does not split into linear factors mod t
+    pub fn new(params: &Parameters) -> Result<Self, RingLweError> {
+        let (n, t) = (params.n, params.t);
+        if !is_prime(t) || (t - 1) % (2 * n as i64) != 0 {
+            return Err(RingLweError::InvalidParameters(format!("t = {} is not a prime that is 1 mod 2n = {}", t, 2 * n)));
+        }
+        let ntt = NttContext::new(n, t, ntt::omega(t, 2 * n));
+
+        // the NTT of x lists z^e for the exponent e of each evaluation
+        let mut x = vec![0; n];
+        x[1] = 1;
+        ntt.forward(&mut x);
+        let index: HashMap<i64, usize> = x.into_iter().enumerate().map(|(i, z_e)| (z_e, i)).collect();
+        let mut powers = vec![1; 2 * n]; // z^e for e in [0, 2n)
+        for e in 1..2 * n {
+            powers[e] = powers[e - 1] * ntt.psi() % t;
+        }
+
+        // row 0 holds the exponents 3^j and row 1 the exponents -3^j mod 2n
+        let mut slots = vec![0; n];
+        let mut e = 1;
+        for j in 0..n / 2 {
+            slots[j] = index[&powers[e]];
+            slots[j + n / 2] = index[&powers[2 * n - e]];
+            e = e * 3 % (2 * n);
+        }
+        Ok(BatchEncoder { n, t, ntt, slots })
+    }
+
+    /// Number of slots n
+    pub fn slot_count(&self) -> usize {
+        self.n
+    }
+
+    /// Encode values into the slots of a plaintext polynomial
+    /// # Arguments:
+    /// * `values` - at most n values in [0, t), row 0 first; missing slots are zero
+    /// # Returns:
+    /// polynomial with coefficients in [0, t) taking the values in the slots, or an error if
+    /// there are too many values or one is out of range
+    pub fn encode(&self, values: &[u64]) -> Result<Polynomial<i64>, RingLweError> {
+        if values.len() > self.n {
+            return Err(RingLweError::LengthMismatch { expected: self.n, found: values.len() });
+        }
+        if let Some((index, &value)) = values.iter().enumerate().find(|(_, &v)| v >= self.t as u64) {
+            return Err(RingLweError::InvalidCoefficient { index, value: value as i64, modulus: self.t });
+        }
+        let mut evals = vec![0; self.n];
+        for (&slot, &value) in self.slots.iter().zip(values) {
+            evals[slot] = value as i64;
+        }
+        self.ntt.inverse(&mut evals);
+        Ok(Polynomial::new(evals))
+    }
+
+    /// Decode a plaintext polynomial into the values of its n slots, row 0 first
+    pub fn decode(&self, poly: &Polynomial<i64>) -> Vec<u64> {
+        let ring = RingPoly::<u32>::from_poly(poly, self.n, self.t);
+        let mut evals: Vec<i64> = ring.coeffs().iter().map(|&c| c as i64).collect();
+        self.ntt.forward(&mut evals);
+        self.slots.iter().map(|&slot| evals[slot] as u64).collect()
+    }
+}
@@ -1,5 +1,61 @@
-use crate::utils::{Parameters, mod_coeffs, polymul_fast, polyadd, gen_ternary_poly,compress,decompress};
+use crate::utils::{Parameters, RingPoly, CoeffWord, WireFormat, compress_d, gen_ternary_poly_with_rng, gen_error_poly_with_rng, serialize_coeffs, expand_seed, SeedDomain, SeedStreams};
+use crate::types::{PublicKey, Ciphertext, CompressedCiphertext};
+use crate::error::RingLweError;
+use crate::format::{ObjectType, seal};
+use crate::codec::encode_message;
 use polynomial_ring::Polynomial;
+use rand::{CryptoRng, RngCore};
+
+impl<W: CoeffWord> PublicKey<W> {
+    /// Encrypt a polynomial using the public key
+    /// # Arguments:
+    /// * `m` - plaintext polynomial
+    /// * `seed` - random seed
+    /// # Returns:
+    /// ciphertext under the parameters of the public key
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let m = polynomial_ring::Polynomial::new(vec![1, 0, 1]);
+    /// let ct = pk.encrypt(&m, None);
+    /// ```
+    pub fn encrypt(&self, m: &Polynomial<i64>, seed: Option<u64>) -> Ciphertext<W> {
+        self.encrypt_from(m, &mut SeedStreams::Seed(seed))
+    }
+
+    /// Encrypt a polynomial using the public key and a caller-supplied random number generator
+    /// # Arguments:
+    /// * `m` - plaintext polynomial
+    /// * `rng` - cryptographically secure random number generator
+    /// # Returns:
+    /// ciphertext under the parameters of the public key
+    pub fn encrypt_with_rng<R: RngCore + CryptoRng>(&self, m: &Polynomial<i64>, rng: &mut R) -> Ciphertext<W> {
+        self.encrypt_from(m, &mut SeedStreams::Rng(rng))
+    }
+
+    /// Encrypt a polynomial, drawing each random polynomial from its own stream
+    fn encrypt_from(&self, m: &Polynomial<i64>, streams: &mut SeedStreams) -> Ciphertext<W> {
+        let params = self.params();
+        let (n,q,t) = (params.n, params.q, params.t);
+        let ntt = params.ntt();
+        let to_ring = |x: &Polynomial<i64>| RingPoly::<W>::from_poly(x, n, q);
+        // Scale the plaintext polynomial. use floor(m*q/t) rather than floor (q/t)*m
+        let scaled_m = to_ring(&(m * q / t));
+
+        // Generate random polynomials
+        let e1 = to_ring(&gen_error_poly_with_rng(params, &mut streams.stream(SeedDomain::Error1)));
+        let e2 = to_ring(&gen_error_poly_with_rng(params, &mut streams.stream(SeedDomain::Error2)));
+        let u = to_ring(&gen_ternary_poly_with_rng(n, &mut streams.stream(SeedDomain::Ephemeral)));
+
+        // Compute ciphertext components, transforming u once against the stored key
+        let u_ntt = u.to_ntt(&ntt);
+        let ct0 = &(&RingPoly::from_ntt(&(self.b_ntt() * &u_ntt), &ntt) + &e1) + &scaled_m;
+        let ct1 = &RingPoly::from_ntt(&(self.a_ntt() * &u_ntt), &ntt) + &e2;
+
+        Ciphertext::from_ring(ct0, ct1, params).with_noise(params.fresh_noise_std())
+    }
+}
 
 /// Encrypt a polynomial using the public key
 /// # Arguments:
@@ -8,7 +64,7 @@ use polynomial_ring::Polynomial;
 /// * `params` - ring-LWE parameters
 /// * `seed` - random seed
 /// # Returns:
-///	(ciphertext component 0, ciphertext component 1)
+/// (ciphertext component 0, ciphertext component 1)
 /// # Example:
 /// ```
 /// let params = ring_lwe::utils::Parameters::default();
@@ -22,20 +78,26 @@ pub fn encrypt(
     params: &Parameters,       //parameters (n,q,t,f)
     seed: Option<u64>            // Seed for random number generator
 ) -> [Polynomial<i64>; 2] {
-    let (n,q,t,f,omega) = (params.n, params.q, params.t, &params.f, params.omega);
-    // Scale the plaintext polynomial. use floor(m*q/t) rather than floor (q/t)*m
-    let scaled_m = mod_coeffs(m * q / t, q);
-
-    // Generate random polynomials
-    let e1 = gen_ternary_poly(n, seed);
-    let e2 = gen_ternary_poly(n, seed);
-    let u = gen_ternary_poly(n, seed);
-
-    // Compute ciphertext components
-    let ct0 = polyadd(&polyadd(&polymul_fast(&pk[0], &u, q, f, omega), &e1, q, f),&scaled_m,q,f);
-    let ct1 = polyadd(&polymul_fast(&pk[1], &u, q, f, omega), &e2, q, f);
+    let pk = PublicKey::new(pk[0].clone(), pk[1].clone(), params);
+    pk.encrypt(m, seed).into_array()
+}
 
-    [ct0, ct1]
+/// Encrypt a polynomial using the public key and a caller-supplied random number generator
+/// # Arguments:
+/// * `pk` - public key as an array of two Polynomials
+/// * `m` - plaintext polynomial
+/// * `params` - ring-LWE parameters
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// (ciphertext component 0, ciphertext component 1)
+pub fn encrypt_with_rng<R: RngCore + CryptoRng>(
+    pk: &[Polynomial<i64>; 2],
+    m: &Polynomial<i64>,
+    params: &Parameters,
+    rng: &mut R
+) -> [Polynomial<i64>; 2] {
+    let pk = PublicKey::new(pk[0].clone(), pk[1].clone(), params);
+    pk.encrypt_with_rng(m, rng).into_array()
 }
 
 /// Encrypt a string using the public key
@@ -45,44 +107,143 @@ pub fn encrypt(
 /// * `params` - ring-LWE parameters
 /// * `seed` - random seed
 /// # Returns:
-///	encrypted message as a base64 encoded string
+/// encrypted message as a base64 encoded string, or an error if the public key is malformed
 /// # Example:
 /// ```
 /// let params = ring_lwe::utils::Parameters::default();
 /// let keys = ring_lwe::keygen::keygen_string(&params, None);
 /// let pk_string = keys.get("public").unwrap();
 /// let message = String::from("hello");
-/// let ciphertext_string = ring_lwe::encrypt::encrypt_string(pk_string, &message, &params, None);
+/// let ciphertext_string = ring_lwe::encrypt::encrypt_string(pk_string, &message, &params, None).unwrap();
 /// ```
-pub fn encrypt_string(pk_base64: &String, message: &String, params: &Parameters, seed: Option<u64>) -> String {
-    // Decode the Base64 public key string
-    let pk_arr: Vec<i64> = decompress(pk_base64);
-
-    // Split the public key into two polynomials
-    let pk_b = Polynomial::new(pk_arr[..params.n].to_vec());
-    let pk_a = Polynomial::new(pk_arr[params.n..].to_vec());
-    let pk = [pk_b, pk_a];
-
-    // Convert each byte into its 8-bit representation (MSB first)
-    let message_bits: Vec<i64> = message
-        .bytes()
-        .flat_map(|byte| (0..8).rev().map(move |i| ((byte >> i) & 1) as i64))
-        .collect();
+pub fn encrypt_string(pk_base64: &str, message: &str, params: &Parameters, seed: Option<u64>) -> Result<String, RingLweError> {
+    encrypt_string_with_format(pk_base64, message, params, seed, WireFormat::Bincode)
+}
+
+/// Encrypt a string using the public key, with the key and ciphertext in the given wire format
+/// # Arguments:
+/// * `pk_string` - public key as a base64 encoded string
+/// * `message` - message to encrypt
+/// * `params` - ring-LWE parameters
+/// * `seed` - random seed
+/// * `format` - wire format of the public key and the ciphertext
+/// # Returns:
+/// encrypted message as a base64 encoded string, or an error if the public key is malformed
+/// # Example:
+/// ```
+/// use ring_lwe::utils::WireFormat;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let keys = ring_lwe::keygen::keygen_string_with_format(&params, None, WireFormat::Packed);
+/// let ciphertext_string = ring_lwe::encrypt::encrypt_string_with_format(&keys["public"], "hello", &params, None, WireFormat::Packed).unwrap();
+/// ```
+pub fn encrypt_string_with_format(pk_base64: &str, message: &str, params: &Parameters, seed: Option<u64>, format: WireFormat) -> Result<String, RingLweError> {
+    encrypt_bytes(pk_base64, message.as_bytes(), params, seed, format)
+}
+
+/// Encrypt arbitrary bytes using the public key
+/// # Arguments:
+/// * `pk_string` - public key as a base64 encoded string
+/// * `message` - bytes to encrypt
+/// * `params` - ring-LWE parameters
+/// * `seed` - random seed
+/// * `format` - wire format of the public key and the ciphertext
+/// # Returns:
+/// encrypted message as a base64 encoded string, or an error if the public key is malformed
+/// # Example:
+/// ```
+/// use ring_lwe::utils::WireFormat;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let keys = ring_lwe::keygen::keygen_string(&params, None);
+/// let ciphertext_string = ring_lwe::encrypt::encrypt_bytes(&keys["public"], &[0, 159, 146, 150, 0], &params, None, WireFormat::Bincode).unwrap();
+/// ```
+pub fn encrypt_bytes(pk_base64: &str, message: &[u8], params: &Parameters, seed: Option<u64>, format: WireFormat) -> Result<String, RingLweError> {
+    // Decode the Base64 public key string and split it into two polynomials
+    let pk = PublicKey::decode(pk_base64, params, format)?;
+
+    // Encode the length-prefixed message into plaintext polynomials of size `n`
+    let message_blocks = encode_message(message, params);
 
-    // Convert bits into a vector of Polynomials
-    let message_blocks: Vec<Polynomial<i64>> = message_bits
-        .chunks(params.n) // Pack bits into polynomials of size `n`
-        .map(|chunk| Polynomial::new(chunk.to_vec()))
+    // Encrypt each integer message block, deriving a distinct seed per block
+    let ciphertexts: Vec<Ciphertext> = message_blocks
+        .iter()
+        .enumerate()
+        .map(|(i, message_block)| pk.encrypt(message_block, expand_seed(seed, SeedDomain::Block(i as u64))))
         .collect();
 
-    // Encrypt each integer message block
-    let mut ciphertext_list: Vec<i64> = Vec::new();
-    for message_block in message_blocks {
-        let ciphertext = encrypt(&pk, &message_block, &params, seed);
-        ciphertext_list.extend(ciphertext[0].coeffs());
-        ciphertext_list.extend(ciphertext[1].coeffs());
-    }
+    // Serialize the concatenated blocks to binary and wrap them in a Base64 container
+    let payload = match format {
+        WireFormat::Bincode => serialize_coeffs(&ciphertexts.iter().flat_map(|ct| ct.to_coeffs()).collect::<Vec<i64>>()),
+        WireFormat::Packed => ciphertexts.iter().flat_map(|ct| ct.to_packed()).collect(),
+    };
+    Ok(seal(ObjectType::Ciphertext, params, format, &payload))
+}
+
+/// Round a ciphertext to du bits per coefficient of c1 and dv bits per coefficient of c0
+/// # Arguments:
+/// * `ct` - ciphertext
+/// * `du` - bits kept per coefficient of c1 = a*u + e2
+/// * `dv` - bits kept per coefficient of c0 = b*u + e1 + m*q/t
+/// # Returns:
+/// compressed ciphertext, or an error if du or dv is not in [1, ceil(log2 q)]
+/// # Example:
+/// ```
+/// let params = ring_lwe::utils::Parameters::default();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let m = polynomial_ring::Polynomial::new(vec![1, 0, 1]);
+/// let compressed = ring_lwe::encrypt::compress_ciphertext(&pk.encrypt(&m, None), 10, 4).unwrap();
+/// let ct = ring_lwe::decrypt::decompress_ciphertext(&compressed);
+/// assert_eq!(sk.decrypt(&ct).unwrap(), m);
+/// ```
+pub fn compress_ciphertext<W: CoeffWord>(ct: &Ciphertext<W>, du: u32, dv: u32) -> Result<CompressedCiphertext, RingLweError> {
+    let q = ct.params().q;
+    let round = |x: Vec<i64>, d: u32| x.into_iter().map(|c| compress_d(c, q, d)).collect();
+    CompressedCiphertext::new(round(ct.c0().to_centered(), dv), round(ct.c1().to_centered(), du), du, dv, ct.params())
+}
 
-    // Serialize the ciphertext list to binary and encode as Base64
-    compress(&ciphertext_list)
-}
\ No newline at end of file
+/// Extra decryption failure probability per message block caused by compressing to (du, dv) bits
+///
+/// Models every noise term as Gaussian and takes a union bound over the n coefficients of
+/// c0 + c1*sk = m*q/t - e*u + e1 + e2*sk, which decrypts wrongly once the noise exceeds q/(2t).
+/// Rounding to d bits adds a roughly uniform error of width q/2^d to c0, and to c1 before it
+/// is multiplied by the ternary secret.
+/// # Arguments:
+/// * `params` - ring-LWE parameters
+/// * `du` - bits kept per coefficient of c1
+/// * `dv` - bits kept per coefficient of c0
+/// # Returns:
+/// failure probability with compression minus the failure probability without it
+/// # Example:
+/// ```
+/// let params = ring_lwe::utils::Parameters::default();
+/// assert!(ring_lwe::encrypt::compression_failure_probability(&params, 10, 4) < 1e-30);
+/// assert!(ring_lwe::encrypt::compression_failure_probability(&params, 4, 2) > 0.5);
+/// ```
+pub fn compression_failure_probability(params: &Parameters, du: u32, dv: u32) -> f64 {
+    let (n, q, t) = (params.n as f64, params.q as f64, params.t as f64);
+    let var_e = params.error_dist.variance(params.sigma);
+    let var_ternary = 2.0 / 3.0;
+
+    // variance of the rounding error at d bits, zero once 2^d >= q makes rounding exact
+    let rounding = |d: u32| {
+        let step = q / 2f64.powi(d as i32);
+        if step <= 1.0 { 0.0 } else { step * step / 12.0 }
+    };
+    let var_base = 2.0 * n * var_e * var_ternary + var_e;
+    let var_compressed = var_base + n * var_ternary * rounding(du) + rounding(dv);
+
+    // P(|noise| > q/(2t)) for each coefficient, union bound over the block
+    let bound = q / (2.0 * t);
+    let failure = |var: f64| (n * erfc(bound / (2.0 * var).sqrt())).min(1.0);
+    (failure(var_compressed) - failure(var_base)).max(0.0)
+}
+
+/// Complementary error function, with fractional error below 1.2e-7 (Numerical Recipes erfcc)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223 + t * (1.00002368 + t * (0.37409196 + t * (0.09678418
+        + t * (-0.18628806 + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587
+        + t * (-0.82215223 + t * 0.17087277))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+
+/// Errors returned by the fallible ring-LWE API
+#[derive(Debug)]
+pub enum RingLweError {
+    /// Base64 or binary decoding failed
+    Decode(String),
+    /// Wrong number of coefficients in a key or ciphertext
+    LengthMismatch { expected: usize, found: usize },
+    /// Key or ciphertext was produced under different parameters
+    ParameterMismatch(String),
+    /// Parameter combination violates an invariant of the scheme
+    InvalidParameters(String),
+    /// Coefficient outside the range allowed by the modulus
+    InvalidCoefficient { index: usize, value: i64, modulus: i64 },
+    /// Reading or writing a key or ciphertext failed
+    Io(std::io::Error),
+}
+
+impl fmt::Display for RingLweError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RingLweError::Decode(msg) => write!(f, "decode error: {}", msg),
+            RingLweError::LengthMismatch { expected, found } => {
+                write!(f, "length mismatch: expected {} coefficients, found {}", expected, found)
+            }
+            RingLweError::ParameterMismatch(msg) => write!(f, "parameter mismatch: {}", msg),
+            RingLweError::InvalidParameters(msg) => write!(f, "invalid parameters: {}", msg),
+            RingLweError::InvalidCoefficient { index, value, modulus } => {
+                write!(f, "invalid coefficient {} at index {} for modulus {}", value, index, modulus)
+            }
+            RingLweError::Io(err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RingLweError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RingLweError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RingLweError {
+    fn from(err: std::io::Error) -> Self {
+        RingLweError::Io(err)
+    }
+}
+
+impl From<base64::DecodeError> for RingLweError {
+    fn from(err: base64::DecodeError) -> Self {
+        RingLweError::Decode(err.to_string())
+    }
+}
+
+impl From<bincode::Error> for RingLweError {
+    fn from(err: bincode::Error) -> Self {
+        RingLweError::Decode(err.to_string())
+    }
+}
@@ -0,0 +1,416 @@
+use crate::utils::{Parameters, NttContext, NttPoly, RingPoly, CoeffWord, mod_coeffs, mod_inverse, nearest_int};
+use crate::types::{Ciphertext, Ciphertext3, RelinKey, GaloisKeys};
+use crate::error::RingLweError;
+use polynomial_ring::Polynomial;
+
+/// Add two ciphertexts
+/// # Arguments:
+/// * `a` - ciphertext of m_a
+/// * `b` - ciphertext of m_b
+/// # Returns:
+/// ciphertext of m_a + m_b mod t, or an error if the parameters differ
+/// # Example:
+/// ```
+/// let params = ring_lwe::utils::Parameters::default();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let a = pk.encrypt(&polynomial_ring::Polynomial::new(vec![1, 0, 1]), None);
+/// let b = pk.encrypt(&polynomial_ring::Polynomial::new(vec![0, 0, 1]), None);
+/// let sum = ring_lwe::evaluate::add(&a, &b).unwrap();
+/// assert_eq!(sk.decrypt(&sum).unwrap(), polynomial_ring::Polynomial::new(vec![1]));
+/// ```
+pub fn add<W: CoeffWord>(a: &Ciphertext<W>, b: &Ciphertext<W>) -> Result<Ciphertext<W>, RingLweError> {
+    a.params().check_match(b.params())?;
+    Ok(Ciphertext::from_ring(a.c0() + b.c0(), a.c1() + b.c1(), a.params()).with_noise(a.noise_estimate().hypot(b.noise_estimate())))
+}
+
+/// Subtract two ciphertexts
+/// # Arguments:
+/// * `a` - ciphertext of m_a
+/// * `b` - ciphertext of m_b
+/// # Returns:
+/// ciphertext of m_a - m_b mod t, or an error if the parameters differ
+pub fn sub<W: CoeffWord>(a: &Ciphertext<W>, b: &Ciphertext<W>) -> Result<Ciphertext<W>, RingLweError> {
+    a.params().check_match(b.params())?;
+    Ok(Ciphertext::from_ring(a.c0() - b.c0(), a.c1() - b.c1(), a.params()).with_noise(a.noise_estimate().hypot(b.noise_estimate())))
+}
+
+/// Negate a ciphertext
+/// # Arguments:
+/// * `a` - ciphertext of m
+/// # Returns:
+/// ciphertext of -m mod t
+pub fn negate<W: CoeffWord>(a: &Ciphertext<W>) -> Ciphertext<W> {
+    Ciphertext::from_ring(-a.c0(), -a.c1(), a.params()).with_noise(a.noise_estimate())
+}
+
+/// Add a plaintext to a ciphertext
+/// # Arguments:
+/// * `ct` - ciphertext of m
+/// * `plain` - plaintext polynomial in Z_t[X]/(x^n+1)
+/// # Returns:
+/// ciphertext of m + plain mod t
+/// # Example:
+/// ```
+/// use polynomial_ring::Polynomial;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let ct = pk.encrypt(&Polynomial::new(vec![1, 0, 1]), None);
+/// let sum = ring_lwe::evaluate::add_plain(&ct, &Polynomial::new(vec![1, 1]));
+/// assert_eq!(sk.decrypt(&sum).unwrap(), Polynomial::new(vec![0, 1, 1]));
+/// ```
+pub fn add_plain<W: CoeffWord>(ct: &Ciphertext<W>, plain: &Polynomial<i64>) -> Ciphertext<W> {
+    Ciphertext::from_ring(ct.c0() + &scale_plain(plain, ct.params()), ct.c1().clone(), ct.params())
+        .with_noise(plain_rounding_noise(ct))
+}
+
+/// Subtract a plaintext from a ciphertext
+/// # Arguments:
+/// * `ct` - ciphertext of m
+/// * `plain` - plaintext polynomial in Z_t[X]/(x^n+1)
+/// # Returns:
+/// ciphertext of m - plain mod t
+pub fn sub_plain<W: CoeffWord>(ct: &Ciphertext<W>, plain: &Polynomial<i64>) -> Ciphertext<W> {
+    Ciphertext::from_ring(ct.c0() - &scale_plain(plain, ct.params()), ct.c1().clone(), ct.params())
+        .with_noise(plain_rounding_noise(ct))
+}
+
+/// Multiply a ciphertext by a plaintext
+///
+/// The plaintext is not scaled; its centered coefficients multiply both components
+/// through the NTT, so the noise grows by at most the l1 norm of the plaintext.
+/// # Arguments:
+/// * `ct` - ciphertext of m
+/// * `plain` - plaintext polynomial in Z_t[X]/(x^n+1)
+/// # Returns:
+/// ciphertext of m * plain mod t
+/// # Example:
+/// ```
+/// use polynomial_ring::Polynomial;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let ct = pk.encrypt(&Polynomial::new(vec![1, 0, 1]), None);
+/// let product = ring_lwe::evaluate::multiply_plain(&ct, &Polynomial::new(vec![0, 1]));
+/// assert_eq!(sk.decrypt(&product).unwrap(), Polynomial::new(vec![0, 1, 0, 1]));
+/// ```
+pub fn multiply_plain<W: CoeffWord>(ct: &Ciphertext<W>, plain: &Polynomial<i64>) -> Ciphertext<W> {
+    let params = ct.params();
+    let (n, q, t) = (params.n, params.q, params.t);
+    let ntt = params.ntt();
+    let centered = Polynomial::new(plain.coeffs().iter().map(|&c| {
+        let r = c.rem_euclid(t);
+        if r > t / 2 { r - t } else { r }
+    }).collect());
+    let plain_ntt = RingPoly::<W>::from_poly(&centered, n, q).to_ntt(&ntt);
+
+    // each noise coefficient becomes a sum of noise coefficients weighted by the plaintext;
+    // the l1 norm bounds this even once earlier products have correlated the noise
+    let norm = centered.coeffs().iter().map(|&c| c.abs() as f64).sum::<f64>();
+    Ciphertext::from_ring(ct.c0().mul_ntt(&plain_ntt, &ntt), ct.c1().mul_ntt(&plain_ntt, &ntt), params)
+        .with_noise(ct.noise_estimate() * norm)
+}
+
+/// Noise estimate after adding floor(m*q/t), which is off from m*q/t by less than 1
+fn plain_rounding_noise<W: CoeffWord>(ct: &Ciphertext<W>) -> f64 {
+    (ct.noise_estimate().powi(2) + 1.0 / 12.0).sqrt()
+}
+
+/// Lift a plaintext into Z_q as floor(m*q/t), the same scaling encryption applies
+fn scale_plain<W: CoeffWord>(plain: &Polynomial<i64>, params: &Parameters) -> RingPoly<W> {
+    let (n, q, t) = (params.n, params.q, params.t);
+    let reduced = mod_coeffs(plain.clone(), t);
+    RingPoly::from_poly(&(&reduced * q / t), n, q)
+}
+
+/// Multiply two ciphertexts
+///
+/// Computes the tensor product (a0*b0, a0*b1 + a1*b0, a1*b1) over the integers from the
+/// centered coefficients, with NTTs modulo three auxiliary primes and the CRT in
+/// O(n log n), then scales it by t/q and rounds back into Z_q. The noise grows by a
+/// factor of roughly t*n per multiplication, so q must be much larger than the default.
+/// # Arguments:
+/// * `a` - ciphertext of m_a
+/// * `b` - ciphertext of m_b
+/// # Returns:
+/// three-component ciphertext of m_a * m_b mod t, or an error if the parameters differ
+/// # Example:
+/// ```
+/// use polynomial_ring::Polynomial;
+/// let params = ring_lwe::utils::Parameters::builder().q(1073479681).build().unwrap();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let rlk = sk.relin_key(16, None).unwrap();
+/// let a = pk.encrypt(&Polynomial::new(vec![1, 0, 1]), None);
+/// let b = pk.encrypt(&Polynomial::new(vec![0, 0, 1]), None);
+/// let product = ring_lwe::evaluate::multiply(&a, &b).unwrap();
+/// let product = ring_lwe::evaluate::relinearize(&product, &rlk).unwrap();
+/// assert_eq!(sk.decrypt(&product).unwrap(), Polynomial::new(vec![0, 0, 1, 0, 1]));
+/// ```
+pub fn multiply<W: CoeffWord>(a: &Ciphertext<W>, b: &Ciphertext<W>) -> Result<Ciphertext3<W>, RingLweError> {
+    a.params().check_match(b.params())?;
+    let params = a.params();
+    let (n, q, t) = (params.n, params.q, params.t);
+    let (a0, a1) = (a.c0().to_centered(), a.c1().to_centered());
+    let (b0, b1) = (b.c0().to_centered(), b.c1().to_centered());
+
+    let [c0, c1, c2] = tensor_product([&a0, &a1], [&b0, &b1], &params.tensor_ntt());
+
+    // round(t*x/q) mod q for each coefficient
+    let (q, t) = (q as i128, t as i128);
+    let scale = |x: Vec<i128>| {
+        let coeffs = x.iter().map(|&v| (2 * t * v + q).div_euclid(2 * q).rem_euclid(q) as i64).collect();
+        RingPoly::<W>::from_poly(&Polynomial::new(coeffs), n, q as i64)
+    };
+
+    // With c_a(sk) = m_a*q/t + v_a + q*k_a, the noise of the product is dominated by
+    // t*(k_a*v_b + k_b*v_a), where k has variance about n/18, plus m_a*v_b + m_b*v_a and
+    // the rounding errors of the three components times 1, sk and sk^2.
+    let (n_f, t_f) = (n as f64, t as f64);
+    let noise_var = a.noise_estimate().powi(2) + b.noise_estimate().powi(2);
+    let noise = (t_f * t_f * n_f * (n_f / 18.0 + 1.0 / 12.0) * noise_var + n_f * n_f / 27.0).sqrt();
+    Ok(Ciphertext3::from_ring(scale(c0), scale(c1), scale(c2), params).with_noise(noise))
+}
+
+/// Relinearize a three-component ciphertext back to two components
+///
+/// Splits c2 into base-T digits d_i and adds sum d_i * rlk_i, which replaces c2*sk^2
+/// by an encryption of the same value at the cost of a noise term sum d_i * e_i.
+/// # Arguments:
+/// * `ct` - three-component ciphertext from `multiply`
+/// * `rlk` - relinearization key of the secret key
+/// # Returns:
+/// ciphertext of the same plaintext, or an error if the parameters differ
+pub fn relinearize<W: CoeffWord>(ct: &Ciphertext3<W>, rlk: &RelinKey) -> Result<Ciphertext<W>, RingLweError> {
+    ct.params().check_match(rlk.params())?;
+    let params = ct.params();
+    let (sum_b, sum_a) = key_switch(ct.c2(), rlk.base(), rlk.b_ntt(), rlk.a_ntt(), params);
+    let noise = ct.noise_estimate().hypot(key_switch_noise(rlk.base(), rlk.b_ntt().len(), params));
+    Ok(Ciphertext::from_ring(ct.c0() + &sum_b, ct.c1() + &sum_a, params).with_noise(noise))
+}
+
+/// Apply the automorphism x -> x^k to the plaintext of a ciphertext
+///
+/// Maps (c0, c1) to (c0(x^k), c1(x^k)), which decrypts under sk(x^k), and key
+/// switches c1(x^k) back to sk with the Galois key for k.
+/// # Arguments:
+/// * `ct` - ciphertext of m
+/// * `k` - odd Galois element in [1, 2n)
+/// * `keys` - Galois keys of the secret key, including one for k
+/// # Returns:
+/// ciphertext of m(x^k) mod x^n+1, or an error if the parameters differ or there is no key for k
+/// # Example:
+/// ```
+/// use polynomial_ring::Polynomial;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let keys = sk.galois_keys(&[3], 16, None).unwrap();
+/// let ct = pk.encrypt(&Polynomial::new(vec![0, 1, 1]), None);
+/// let image = ring_lwe::evaluate::apply_galois(&ct, 3, &keys).unwrap();
+/// assert_eq!(sk.decrypt(&image).unwrap(), Polynomial::new(vec![0, 0, 0, 1, 0, 0, 1]));
+/// ```
+pub fn apply_galois<W: CoeffWord>(ct: &Ciphertext<W>, k: usize, keys: &GaloisKeys) -> Result<Ciphertext<W>, RingLweError> {
+    ct.params().check_match(keys.params())?;
+    let params = ct.params();
+    params.check_galois_element(k)?;
+    let (b_ntt, a_ntt) = keys.get(k).ok_or_else(|| {
+        RingLweError::InvalidParameters(format!("no Galois key for element {}", k))
+    })?;
+    let (sum_b, sum_a) = key_switch(&ct.c1().automorphism(k), keys.base(), b_ntt, a_ntt, params);
+    let noise = ct.noise_estimate().hypot(key_switch_noise(keys.base(), b_ntt.len(), params));
+    Ok(Ciphertext::from_ring(&ct.c0().automorphism(k) + &sum_b, sum_a, params).with_noise(noise))
+}
+
+/// Galois element that rotates both rows of batched slots left by `steps`
+///
+/// Batched slots form a 2 x n/2 matrix, and x -> x^(3^steps mod 2n) rotates each row
+/// cyclically; negative steps rotate right.
+/// # Arguments:
+/// * `steps` - number of slots to rotate by
+/// * `n` - degree of the polynomial modulus x^n+1, a power of two of at least 4
+/// # Returns:
+/// Galois element 3^steps mod 2n
+/// # Example:
+/// ```
+/// use ring_lwe::evaluate::row_rotation_element;
+/// assert_eq!(row_rotation_element(1, 1024), 3);
+/// assert_eq!(row_rotation_element(2, 1024), 9);
+/// assert_eq!(row_rotation_element(-1, 1024) * 3 % 2048, 1);
+/// ```
+pub fn row_rotation_element(steps: i64, n: usize) -> usize {
+    let m = 2 * n as u64;
+    // 3 generates a cyclic subgroup of order n/2 of the units mod 2n
+    let mut exponent = steps.rem_euclid(n as i64 / 2) as u64;
+    let (mut base, mut element) = (3 % m, 1 % m);
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            element = element * base % m;
+        }
+        base = base * base % m;
+        exponent >>= 1;
+    }
+    element as usize
+}
+
+/// Galois element that swaps the two rows of batched slots
+/// # Arguments:
+/// * `n` - degree of the polynomial modulus x^n+1
+/// # Returns:
+/// Galois element 2n - 1, i.e. x -> x^-1
+pub fn column_rotation_element(n: usize) -> usize {
+    2 * n - 1
+}
+
+/// Rotate both rows of batched slots left by `steps`
+/// # Arguments:
+/// * `ct` - ciphertext of a batched plaintext
+/// * `steps` - number of slots to rotate by, negative to rotate right
+/// * `keys` - Galois keys including `row_rotation_element(steps, n)`
+/// # Returns:
+/// ciphertext of the rotated slots, or an error if the parameters differ or the key is missing
+pub fn rotate_rows<W: CoeffWord>(ct: &Ciphertext<W>, steps: i64, keys: &GaloisKeys) -> Result<Ciphertext<W>, RingLweError> {
+    apply_galois(ct, row_rotation_element(steps, ct.params().n), keys)
+}
+
+/// Swap the two rows of batched slots
+/// # Arguments:
+/// * `ct` - ciphertext of a batched plaintext
+/// * `keys` - Galois keys including `column_rotation_element(n)`
+/// # Returns:
+/// ciphertext of the swapped slots, or an error if the parameters differ or the key is missing
+pub fn rotate_columns<W: CoeffWord>(ct: &Ciphertext<W>, keys: &GaloisKeys) -> Result<Ciphertext<W>, RingLweError> {
+    apply_galois(ct, column_rotation_element(ct.params().n), keys)
+}
+
+/// Switch a ciphertext down to a smaller modulus of the chain
+///
+/// Scales each coefficient by q_to/q_from and rounds it to the nearest integer. The noise
+/// shrinks by the same factor, plus a rounding term of about sqrt(n/18), so the noise
+/// budget in bits stays about the same until the rounding term dominates, while each
+/// coefficient takes log2(q_to) instead of log2(q_from) bits.
+/// # Arguments:
+/// * `ct` - ciphertext of m under modulus q_from
+/// * `q_from` - current modulus of the ciphertext
+/// * `q_to` - entry of the modulus chain of the ciphertext parameters
+/// # Returns:
+/// ciphertext of m under the parameters `params.at_modulus(q_to)`, or an error if the
+/// ciphertext is not under q_from or q_to is not on its modulus chain
+/// # Example:
+/// ```
+/// use polynomial_ring::Polynomial;
+/// let params = ring_lwe::utils::Parameters::builder().q(1073479681).modulus_chain(&[12289]).build().unwrap();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let ct = pk.encrypt(&Polynomial::new(vec![1, 0, 1]), None);
+/// let small = ring_lwe::evaluate::mod_switch(&ct, 1073479681, 12289).unwrap();
+/// assert_eq!(small.params().q, 12289);
+/// // the secret key is the same ternary polynomial, reduced mod q_to
+/// let sk_small = ring_lwe::types::SecretKey::new(sk.s().to_poly(), small.params());
+/// assert_eq!(sk_small.decrypt(&small).unwrap(), Polynomial::new(vec![1, 0, 1]));
+/// ```
+pub fn mod_switch<W: CoeffWord>(ct: &Ciphertext<W>, q_from: i64, q_to: i64) -> Result<Ciphertext<W>, RingLweError> {
+    if ct.params().q != q_from {
+        return Err(RingLweError::ParameterMismatch(format!("q: expected {}, found {}", q_from, ct.params().q)));
+    }
+    let params = ct.params().at_modulus(q_to)?;
+    let (n, t) = (params.n, params.t);
+    let scale = |c: &RingPoly<W>| {
+        let coeffs = c.to_centered().iter().map(|&x| nearest_int(x * q_to, q_from)).collect();
+        RingPoly::<W>::from_poly(&Polynomial::new(coeffs), n, q_to)
+    };
+
+    // q_to/q_from * floor(q_from/t) differs from floor(q_to/t) by eps in (-1, 1), which
+    // leaves eps*m, and the rounding errors r0 + r1*sk have variance (2n/3 + 1)/12.
+    let eps = ((q_to % t) as f64 - q_to as f64 * (q_from % t) as f64 / q_from as f64) / t as f64;
+    let ratio = q_to as f64 / q_from as f64;
+    let rounding_var = (2.0 * n as f64 / 3.0 + 1.0) / 12.0;
+    let noise = (ratio * ct.noise_estimate()).hypot(rounding_var.sqrt()) + eps.abs() * (t - 1) as f64;
+    Ok(Ciphertext::from_ring(scale(ct.c0()), scale(ct.c1()), &params).with_noise(noise))
+}
+
+/// Switch a ciphertext down to the next modulus of the chain
+/// # Arguments:
+/// * `ct` - ciphertext of m
+/// # Returns:
+/// ciphertext of m under the first modulus of the chain, or an error if the chain is empty
+pub fn mod_switch_to_next<W: CoeffWord>(ct: &Ciphertext<W>) -> Result<Ciphertext<W>, RingLweError> {
+    let q_to = *ct.params().modulus_chain.first().ok_or_else(|| {
+        RingLweError::InvalidParameters(format!("no modulus below q = {} on the modulus chain", ct.params().q))
+    })?;
+    mod_switch(ct, ct.params().q, q_to)
+}
+
+/// Key switch a polynomial c from a key s' to sk with encryptions (b_i, a_i) of T^i*s'
+///
+/// Splits c into base-T digits d_i and returns (sum d_i * b_i, sum d_i * a_i), whose
+/// decryption under sk is c*s' plus the noise term sum d_i * e_i.
+fn key_switch<W: CoeffWord>(c: &RingPoly<W>, base: u32, b_ntt: &[NttPoly], a_ntt: &[NttPoly], params: &Parameters) -> (RingPoly<W>, RingPoly<W>) {
+    let (n, q) = (params.n, params.q);
+    let ntt = params.ntt();
+    let base = base as u64;
+
+    // Accumulate sum d_i * b_i and sum d_i * a_i in the NTT domain
+    let mut rest: Vec<u64> = c.coeffs().iter().map(|c| c.to_u64()).collect();
+    let mut sum_b = RingPoly::<W>::zero(n, q).to_ntt(&ntt);
+    let mut sum_a = sum_b.clone();
+    for (b_i, a_i) in b_ntt.iter().zip(a_ntt) {
+        let digit = Polynomial::new(rest.iter().map(|&c| (c % base) as i64).collect());
+        rest.iter_mut().for_each(|c| *c /= base);
+        let digit_ntt = RingPoly::<W>::from_poly(&digit, n, q).to_ntt(&ntt);
+        sum_b = &sum_b + &(&digit_ntt * b_i);
+        sum_a = &sum_a + &(&digit_ntt * a_i);
+    }
+    (RingPoly::from_ntt(&sum_b, &ntt), RingPoly::from_ntt(&sum_a, &ntt))
+}
+
+/// Standard deviation of the key switching noise sum d_i * e_i, with E[d^2] about T^2/3 for digits in [0, T)
+fn key_switch_noise(base: u32, digits: usize, params: &Parameters) -> f64 {
+    let var_e = params.error_dist.variance(params.sigma);
+    (digits as f64 * params.n as f64 * (base as f64).powi(2) / 3.0 * var_e).sqrt()
+}
+
+/// Tensor product (x0*y0, x0*y1 + x1*y0, x1*y1) of two pairs of polynomials in Z[X]/(x^n+1)
+///
+/// Computes each component modulo every prime of `ntts` with the NTT and lifts it back with
+/// Garner's CRT to the centered residue modulo their product, which is exact as long as that
+/// product exceeds twice the largest coefficient.
+fn tensor_product(x: [&[i64]; 2], y: [&[i64]; 2], ntts: &[NttContext]) -> [Vec<i128>; 3] {
+    let n = x[0].len();
+
+    // Residues of the three components modulo each prime
+    let residues: Vec<[Vec<i64>; 3]> = ntts.iter().map(|ntt| {
+        let p = ntt.q();
+        let forward = |v: &[i64]| {
+            let mut v = v.to_vec();
+            ntt.forward(&mut v);
+            v
+        };
+        let (x0, x1, y0, y1) = (forward(x[0]), forward(x[1]), forward(y[0]), forward(y[1]));
+        let mut c0: Vec<i64> = (0..n).map(|i| x0[i] * y0[i] % p).collect();
+        let mut c1: Vec<i64> = (0..n).map(|i| (x0[i] * y1[i] % p + x1[i] * y0[i] % p) % p).collect();
+        let mut c2: Vec<i64> = (0..n).map(|i| x1[i] * y1[i] % p).collect();
+        for c in [&mut c0, &mut c1, &mut c2] {
+            ntt.inverse(c);
+        }
+        [c0, c1, c2]
+    }).collect();
+
+    // (p_1 * ... * p_(j-1))^-1 mod p_j for each prime p_j
+    let mut inverses = vec![];
+    let mut modulus = 1i128;
+    for ntt in ntts {
+        let p = ntt.q();
+        inverses.push(mod_inverse((modulus % p as i128) as i64, p).expect("tensor product primes are distinct") as i128);
+        modulus *= p as i128;
+    }
+
+    // Garner: add (r_j - value) * inverse_j mod p_j times the product of the earlier primes
+    let lift = |component: usize| -> Vec<i128> {
+        (0..n).map(|i| {
+            let (mut value, mut product) = (0i128, 1i128);
+            for ((ntt, r), inverse) in ntts.iter().zip(&residues).zip(&inverses) {
+                let p = ntt.q() as i128;
+                value += product * ((r[component][i] as i128 - value).rem_euclid(p) * inverse % p);
+                product *= p;
+            }
+            if 2 * value > modulus { value - modulus } else { value }
+        }).collect()
+    };
+    [lift(0), lift(1), lift(2)]
+}
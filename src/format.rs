@@ -0,0 +1,348 @@
+use crate::utils::{Parameters, ErrorDistribution, WireFormat, encode_bytes, decode_bytes};
+use crate::error::RingLweError;
+use std::fmt;
+
+/// Magic bytes at the start of every container
+pub const MAGIC: [u8; 4] = *b"RLWE";
+
+/// Current version of the container layout; from version 2 on, packed ciphertexts end
+/// with their noise estimate
+pub const FORMAT_VERSION: u8 = 2;
+
+/// Flag bit marking a trailing CRC-32 of the header and payload
+const FLAG_CHECKSUM: u8 = 1;
+
+/// Number of base64 characters per armored line
+const ARMOR_WIDTH: usize = 64;
+
+/// Size of the fixed header: magic, version, object type, wire format, flags,
+/// n (u32), q (u64), t (u64), error and secret distribution IDs, payload length (u32)
+const HEADER_LEN: usize = 4 + 4 + 4 + 8 + 8 + 2 + 4;
+
+/// Kind of object stored in a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectType {
+    PublicKey,
+    SecretKey,
+    Ciphertext,
+}
+
+impl ObjectType {
+    fn id(self) -> u8 {
+        match self {
+            ObjectType::PublicKey => 1,
+            ObjectType::SecretKey => 2,
+            ObjectType::Ciphertext => 3,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self, RingLweError> {
+        match id {
+            1 => Ok(ObjectType::PublicKey),
+            2 => Ok(ObjectType::SecretKey),
+            3 => Ok(ObjectType::Ciphertext),
+            _ => Err(RingLweError::Decode(format!("unknown object type {}", id))),
+        }
+    }
+
+    /// Label used in the armor lines, e.g. "RING-LWE PUBLIC KEY"
+    pub fn armor_label(self) -> &'static str {
+        match self {
+            ObjectType::PublicKey => "RING-LWE PUBLIC KEY",
+            ObjectType::SecretKey => "RING-LWE SECRET KEY",
+            ObjectType::Ciphertext => "RING-LWE CIPHERTEXT",
+        }
+    }
+
+    fn from_armor_label(label: &str) -> Result<Self, RingLweError> {
+        [ObjectType::PublicKey, ObjectType::SecretKey, ObjectType::Ciphertext]
+            .into_iter()
+            .find(|object_type| object_type.armor_label() == label)
+            .ok_or_else(|| RingLweError::Decode(format!("unknown armor label {}", label)))
+    }
+}
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectType::PublicKey => write!(f, "public key"),
+            ObjectType::SecretKey => write!(f, "secret key"),
+            ObjectType::Ciphertext => write!(f, "ciphertext"),
+        }
+    }
+}
+
+/// Identifier of an error distribution in the container header
+pub fn error_dist_id(dist: &ErrorDistribution) -> u8 {
+    match dist {
+        ErrorDistribution::Ternary => 0,
+        ErrorDistribution::RoundedGaussian => 1,
+        ErrorDistribution::DiscreteGaussian { .. } => 2,
+        ErrorDistribution::CenteredBinomial { .. } => 3,
+    }
+}
+
+/// Identifier of the secret distribution; secrets are always uniform ternary
+const SECRET_DIST_TERNARY: u8 = 0;
+
+fn wire_format_id(format: WireFormat) -> u8 {
+    match format {
+        WireFormat::Bincode => 0,
+        WireFormat::Packed => 1,
+    }
+}
+
+fn wire_format_from_id(id: u8) -> Result<WireFormat, RingLweError> {
+    match id {
+        0 => Ok(WireFormat::Bincode),
+        1 => Ok(WireFormat::Packed),
+        _ => Err(RingLweError::Decode(format!("unknown wire format {}", id))),
+    }
+}
+
+/// Self-describing header of a key or ciphertext container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u8,
+    pub object_type: ObjectType,
+    pub format: WireFormat,      // encoding of the payload
+    pub n: usize,
+    pub q: i64,
+    pub t: i64,
+    pub error_dist: u8,          // see `error_dist_id`
+    pub secret_dist: u8,
+    pub checksum: bool,          // whether a CRC-32 follows the payload
+}
+
+impl Header {
+    /// Header describing an object produced under the given parameters
+    /// # Arguments:
+    /// * `object_type` - kind of object in the payload
+    /// * `params` - ring-LWE parameters
+    /// * `format` - encoding of the payload
+    pub fn new(object_type: ObjectType, params: &Parameters, format: WireFormat) -> Self {
+        Header {
+            version: FORMAT_VERSION,
+            object_type,
+            format,
+            n: params.n,
+            q: params.q,
+            t: params.t,
+            error_dist: error_dist_id(&params.error_dist),
+            secret_dist: SECRET_DIST_TERNARY,
+            checksum: true,
+        }
+    }
+
+    /// Check that the header describes an object of the expected kind under the given parameters
+    /// # Arguments:
+    /// * `object_type` - expected kind of object
+    /// * `params` - ring-LWE parameters the caller is using
+    /// # Returns:
+    /// `Ok(())`, or a parameter mismatch naming the first differing field
+    pub fn check(&self, object_type: ObjectType, params: &Parameters) -> Result<(), RingLweError> {
+        if self.object_type != object_type {
+            return Err(RingLweError::ParameterMismatch(format!("expected a {}, found a {}", object_type, self.object_type)));
+        }
+        let fields = [
+            ("n", self.n as i64, params.n as i64),
+            ("q", self.q, params.q),
+            ("t", self.t, params.t),
+            ("error distribution", self.error_dist as i64, error_dist_id(&params.error_dist) as i64),
+            ("secret distribution", self.secret_dist as i64, SECRET_DIST_TERNARY as i64),
+        ];
+        match fields.iter().find(|(_, found, expected)| found != expected) {
+            Some((name, found, expected)) => Err(RingLweError::ParameterMismatch(
+                format!("{} was written with {} = {}, but {} = {} was given", self.object_type, name, found, name, expected)
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// CRC-32 (IEEE 802.3) of a byte string
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Whether a byte string starts with the container magic
+pub fn is_container(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/// Wrap a payload in a container
+/// # Arguments:
+/// * `header` - description of the payload
+/// * `payload` - encoded key or ciphertext
+/// # Returns:
+/// header, payload and, if `header.checksum` is set, a CRC-32 of both
+pub fn encode_container(header: &Header, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len() + 4);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(header.version);
+    bytes.push(header.object_type.id());
+    bytes.push(wire_format_id(header.format));
+    bytes.push(if header.checksum { FLAG_CHECKSUM } else { 0 });
+    bytes.extend_from_slice(&(header.n as u32).to_le_bytes());
+    bytes.extend_from_slice(&(header.q as u64).to_le_bytes());
+    bytes.extend_from_slice(&(header.t as u64).to_le_bytes());
+    bytes.push(header.error_dist);
+    bytes.push(header.secret_dist);
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    if header.checksum {
+        let crc = crc32(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+    }
+    bytes
+}
+
+/// Split a container into its header and payload
+/// # Arguments:
+/// * `bytes` - container written by `encode_container`
+/// # Returns:
+/// (header, payload), or a decode error if the magic, version, length or checksum is wrong
+pub fn decode_container(bytes: &[u8]) -> Result<(Header, &[u8]), RingLweError> {
+    if !is_container(bytes) {
+        return Err(RingLweError::Decode(String::from("missing container magic")));
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(RingLweError::Decode(String::from("truncated container header")));
+    }
+    let version = bytes[4];
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(RingLweError::Decode(format!("unsupported container version {}", version)));
+    }
+    let le_u32 = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+    let le_u64 = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+    let header = Header {
+        version,
+        object_type: ObjectType::from_id(bytes[5])?,
+        format: wire_format_from_id(bytes[6])?,
+        checksum: bytes[7] & FLAG_CHECKSUM != 0,
+        n: le_u32(8) as usize,
+        q: le_u64(12) as i64,
+        t: le_u64(20) as i64,
+        error_dist: bytes[28],
+        secret_dist: bytes[29],
+    };
+    let payload_len = le_u32(30) as usize;
+    let trailer_len = if header.checksum { 4 } else { 0 };
+    if bytes.len() != HEADER_LEN + payload_len + trailer_len {
+        return Err(RingLweError::Decode(format!(
+            "container holds {} bytes, header announces {}", bytes.len(), HEADER_LEN + payload_len + trailer_len
+        )));
+    }
+    let body_len = HEADER_LEN + payload_len;
+    if header.checksum && crc32(&bytes[..body_len]) != le_u32(body_len) {
+        return Err(RingLweError::Decode(String::from("container checksum mismatch")));
+    }
+    Ok((header, &bytes[HEADER_LEN..body_len]))
+}
+
+/// Wrap a base64 string in PEM-style armor
+/// # Arguments:
+/// * `object_type` - kind of object, named in the BEGIN and END lines
+/// * `base64_str` - base64 encoded key or ciphertext
+/// # Returns:
+/// BEGIN line, the base64 text wrapped at 64 characters, and END line, each ending in a newline
+/// # Example:
+/// ```
+/// use ring_lwe::format::{armor, dearmor, ObjectType};
+/// let text = armor(ObjectType::PublicKey, "AAAA");
+/// assert_eq!(text, "-----BEGIN RING-LWE PUBLIC KEY-----\nAAAA\n-----END RING-LWE PUBLIC KEY-----\n");
+/// assert_eq!(dearmor(&text).unwrap(), (Some(ObjectType::PublicKey), String::from("AAAA")));
+/// ```
+pub fn armor(object_type: ObjectType, base64_str: &str) -> String {
+    let label = object_type.armor_label();
+    let mut text = format!("-----BEGIN {}-----\n", label);
+    for line in base64_str.as_bytes().chunks(ARMOR_WIDTH) {
+        text.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        text.push('\n');
+    }
+    text.push_str(&format!("-----END {}-----\n", label));
+    text
+}
+
+/// Whether a text starts with an armor BEGIN line
+pub fn is_armored(text: &str) -> bool {
+    text.trim_start().starts_with("-----BEGIN ")
+}
+
+/// Strip PEM-style armor, passing bare base64 through
+/// # Arguments:
+/// * `text` - armored text as written by `armor`, or a bare base64 string
+/// # Returns:
+/// (object type named by the armor, if any, base64 text without line breaks),
+/// or a decode error if the armor lines are malformed or do not match
+pub fn dearmor(text: &str) -> Result<(Option<ObjectType>, String), RingLweError> {
+    if !is_armored(text) {
+        return Ok((None, text.trim().to_string()));
+    }
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let label_of = |line: Option<&str>, marker: &str| {
+        line.and_then(|line| line.strip_prefix(marker))
+            .and_then(|rest| rest.strip_suffix("-----"))
+            .map(String::from)
+            .ok_or_else(|| RingLweError::Decode(format!("missing armor line {}...-----", marker)))
+    };
+    let label = label_of(lines.next(), "-----BEGIN ")?;
+    let object_type = ObjectType::from_armor_label(&label)?;
+    let mut body = String::new();
+    for line in lines.by_ref() {
+        if line.starts_with("-----END ") {
+            if label_of(Some(line), "-----END ")? != label {
+                return Err(RingLweError::Decode(format!("armor END line does not match BEGIN {}", label)));
+            }
+            if lines.next().is_some() {
+                return Err(RingLweError::Decode(String::from("text after armor END line")));
+            }
+            return Ok((Some(object_type), body));
+        }
+        body.push_str(line);
+    }
+    Err(RingLweError::Decode(format!("missing armor line -----END {}-----", label)))
+}
+
+/// Wrap a payload in a checksummed container and base64 encode it
+/// # Arguments:
+/// * `object_type` - kind of object in the payload
+/// * `params` - ring-LWE parameters the object was produced under
+/// * `format` - encoding of the payload
+/// * `payload` - encoded key or ciphertext
+pub fn seal(object_type: ObjectType, params: &Parameters, format: WireFormat, payload: &[u8]) -> String {
+    encode_bytes(&encode_container(&Header::new(object_type, params, format), payload))
+}
+
+/// Decode a base64 string, armored or not, and unwrap its payload
+///
+/// Strings without the container magic are read as a bare payload in `format`,
+/// as written before containers existed; their parameters cannot be checked.
+/// # Arguments:
+/// * `text` - container or bare payload as a base64 encoded string, optionally armored
+/// * `object_type` - expected kind of object
+/// * `params` - ring-LWE parameters the caller is using
+/// * `format` - encoding of a bare payload; containers carry their own
+/// # Returns:
+/// (payload encoding, container version or 0 for a bare payload, payload), or an error if
+/// the string is malformed or the parameters differ
+pub fn open(text: &str, object_type: ObjectType, params: &Parameters, format: WireFormat) -> Result<(WireFormat, u8, Vec<u8>), RingLweError> {
+    let (armored_type, base64_str) = dearmor(text)?;
+    if let Some(found) = armored_type.filter(|&found| found != object_type) {
+        return Err(RingLweError::ParameterMismatch(format!("expected a {}, found a {}", object_type, found)));
+    }
+    let bytes = decode_bytes(&base64_str)?;
+    if !is_container(&bytes) {
+        return Ok((format, 0, bytes));
+    }
+    let (header, payload) = decode_container(&bytes)?;
+    header.check(object_type, params)?;
+    Ok((header.format, header.version, payload.to_vec()))
+}
@@ -1,39 +1,225 @@
-use crate::utils::{Parameters, polymul_fast, polyadd, polyinv, gen_ternary_poly, gen_uniform_poly,compress};
+use crate::utils::{Parameters, NttPoly, RingPoly, CoeffWord, WireFormat, gen_ternary_poly_with_rng, gen_uniform_poly_with_rng, gen_error_poly_with_rng, SeedDomain, SeedStreams};
+use crate::types::{PublicKey, SecretKey, RelinKey, GaloisKeys};
+use crate::error::RingLweError;
 use polynomial_ring::Polynomial;
-use std::collections::HashMap;
+use rand::{CryptoRng, RngCore};
+use std::collections::{BTreeMap, HashMap};
+
+impl SecretKey {
+    /// Generate a public and secret key pair
+    /// # Arguments:
+    /// * `params` - ring-LWE parameters
+    /// * `seed` - random seed
+    /// # Returns:
+    /// (public key, secret key)
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// assert_eq!(pk.params(), sk.params());
+    /// ```
+    pub fn generate(params: &Parameters, seed: Option<u64>) -> (PublicKey, SecretKey) {
+        SecretKey::generate_from(params, &mut SeedStreams::Seed(seed))
+    }
+
+    /// Generate a public and secret key pair from a caller-supplied random number generator
+    /// # Arguments:
+    /// * `params` - ring-LWE parameters
+    /// * `rng` - cryptographically secure random number generator
+    /// # Returns:
+    /// (public key, secret key)
+    /// # Example:
+    /// ```
+    /// use rand::SeedableRng;
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(1);
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate_with_rng(&params, &mut rng);
+    /// ```
+    pub fn generate_with_rng<R: RngCore + CryptoRng>(params: &Parameters, rng: &mut R) -> (PublicKey, SecretKey) {
+        SecretKey::generate_from(params, &mut SeedStreams::Rng(rng))
+    }
+
+    /// Generate a public and secret key pair, drawing each polynomial from its own stream
+    fn generate_from(params: &Parameters, streams: &mut SeedStreams) -> (PublicKey, SecretKey) {
+
+        //rename parameters
+        let (n, q) = (params.n, params.q);
+        let to_ring = |x: &Polynomial<i64>| RingPoly::<u32>::from_poly(x, n, q);
+
+        // Generate a public and secret key
+        let sk = SecretKey::from_ring(to_ring(&gen_ternary_poly_with_rng(n, &mut streams.stream(SeedDomain::Secret))), params);
+        let a = to_ring(&gen_uniform_poly_with_rng(n, q, &mut streams.stream(SeedDomain::Uniform)));
+        let e = to_ring(&gen_error_poly_with_rng(params, &mut streams.stream(SeedDomain::Error)));
+        let b = &(-&a.mul_ntt(sk.s_ntt(), &params.ntt())) - &e; // b = -a*sk - e
+
+        // Return public key (b, a) and secret key (sk)
+        (PublicKey::from_ring(b, a, params), sk)
+    }
+}
+
+impl<W: CoeffWord> SecretKey<W> {
+    /// Generate a relinearization key for this secret key
+    /// # Arguments:
+    /// * `base` - digit base T; smaller bases add less noise but need more key polynomials
+    /// * `seed` - random seed
+    /// # Returns:
+    /// relinearization key, or an error if the base is not in [2, q)
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let rlk = sk.relin_key(16, None).unwrap();
+    /// assert_eq!(rlk.b_ntt().len(), 4);
+    /// ```
+    pub fn relin_key(&self, base: u32, seed: Option<u64>) -> Result<RelinKey, RingLweError> {
+        self.relin_key_from(base, &mut SeedStreams::Seed(seed))
+    }
+
+    /// Generate a relinearization key for this secret key from a caller-supplied random number generator
+    /// # Arguments:
+    /// * `base` - digit base T
+    /// * `rng` - cryptographically secure random number generator
+    /// # Returns:
+    /// relinearization key, or an error if the base is not in [2, q)
+    pub fn relin_key_with_rng<R: RngCore + CryptoRng>(&self, base: u32, rng: &mut R) -> Result<RelinKey, RingLweError> {
+        self.relin_key_from(base, &mut SeedStreams::Rng(rng))
+    }
+
+    /// Generate a relinearization key, keying its digits by the `RelinKey` sub-seed
+    fn relin_key_from(&self, base: u32, streams: &mut SeedStreams) -> Result<RelinKey, RingLweError> {
+        check_base(base, self.params().q)?;
+        let s_squared = self.s().mul_ntt(self.s_ntt(), &self.params().ntt());
+        let (b_ntt, a_ntt) = self.key_switch_key(&s_squared, base, &mut streams.child(SeedDomain::RelinKey));
+        Ok(RelinKey::from_ntt(base, b_ntt, a_ntt, self.params()))
+    }
+
+    /// Generate Galois keys for the automorphisms x -> x^k of this secret key
+    /// # Arguments:
+    /// * `elements` - odd Galois elements k in [1, 2n), see `evaluate::row_rotation_element`
+    /// * `base` - digit base T; smaller bases add less noise but need more key polynomials
+    /// * `seed` - random seed
+    /// # Returns:
+    /// Galois keys, or an error if an element is not odd and in [1, 2n) or the base is not in [2, q)
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let keys = sk.galois_keys(&[3, 2047], 16, None).unwrap();
+    /// assert_eq!(keys.elements(), vec![3, 2047]);
+    /// ```
+    pub fn galois_keys(&self, elements: &[usize], base: u32, seed: Option<u64>) -> Result<GaloisKeys, RingLweError> {
+        self.galois_keys_from(elements, base, &mut SeedStreams::Seed(seed))
+    }
+
+    /// Generate Galois keys for this secret key from a caller-supplied random number generator
+    /// # Arguments:
+    /// * `elements` - odd Galois elements k in [1, 2n)
+    /// * `base` - digit base T
+    /// * `rng` - cryptographically secure random number generator
+    /// # Returns:
+    /// Galois keys, or an error if an element is not odd and in [1, 2n) or the base is not in [2, q)
+    pub fn galois_keys_with_rng<R: RngCore + CryptoRng>(&self, elements: &[usize], base: u32, rng: &mut R) -> Result<GaloisKeys, RingLweError> {
+        self.galois_keys_from(elements, base, &mut SeedStreams::Rng(rng))
+    }
+
+    /// Generate Galois keys, keying the digits of each key by its `GaloisKey(k)` sub-seed
+    fn galois_keys_from(&self, elements: &[usize], base: u32, streams: &mut SeedStreams) -> Result<GaloisKeys, RingLweError> {
+        check_base(base, self.params().q)?;
+        for &k in elements {
+            self.params().check_galois_element(k)?;
+        }
+        let mut keys = BTreeMap::new();
+        for &k in elements {
+            keys.entry(k).or_insert_with(|| self.key_switch_key(&self.s().automorphism(k), base, &mut streams.child(SeedDomain::GaloisKey(k as u64))));
+        }
+        Ok(GaloisKeys::from_ntt(base, keys, self.params()))
+    }
+
+    /// Encrypt T^i * target under this secret key for each base-T digit i of q,
+    /// drawing a_i and e_i from the streams of digit i
+    fn key_switch_key(&self, target: &RingPoly<W>, base: u32, streams: &mut SeedStreams) -> (Vec<NttPoly>, Vec<NttPoly>) {
+        let params = self.params();
+        let (n, q) = (params.n, params.q);
+        let ntt = params.ntt();
+        let to_ring = |x: &Polynomial<i64>| RingPoly::<W>::from_poly(x, n, q);
+        let target = target.to_poly();
+
+        let (mut b_ntt, mut a_ntt) = (vec![], vec![]);
+        let mut power = 1; // T^i mod q
+        for i in 0..RelinKey::digits(base, q) as u64 {
+            let a = to_ring(&gen_uniform_poly_with_rng(n, q, &mut streams.stream(SeedDomain::KeySwitchUniform(i))));
+            let e = to_ring(&gen_error_poly_with_rng(params, &mut streams.stream(SeedDomain::KeySwitchError(i))));
+            let b = &(&(-&a.mul_ntt(self.s_ntt(), &ntt)) - &e) + &to_ring(&(&target * power));
+            b_ntt.push(b.to_ntt(&ntt));
+            a_ntt.push(a.to_ntt(&ntt));
+            power = power * base as i64 % q;
+        }
+        (b_ntt, a_ntt)
+    }
+}
+
+/// Check that a key switching digit base is in [2, q)
+fn check_base(base: u32, q: i64) -> Result<(), RingLweError> {
+    if base < 2 || base as i64 >= q {
+        return Err(RingLweError::InvalidParameters(format!("key switching base {} is not in [2, q = {})", base, q)));
+    }
+    Ok(())
+}
+
+/// Generate Galois keys for the automorphisms x -> x^k of a secret key
+/// # Arguments:
+/// * `sk` - secret key
+/// * `elements` - odd Galois elements k in [1, 2n)
+/// * `base` - digit base T
+/// * `seed` - random seed
+/// # Returns:
+/// Galois keys, or an error if an element is not odd and in [1, 2n) or the base is not in [2, q)
+/// # Example:
+/// ```
+/// use ring_lwe::evaluate::{row_rotation_element, column_rotation_element};
+/// let params = ring_lwe::utils::Parameters::default();
+/// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+/// let elements = [row_rotation_element(1, params.n), column_rotation_element(params.n)];
+/// let keys = ring_lwe::keygen::gen_galois_keys(&sk, &elements, 16, None).unwrap();
+/// assert!(keys.contains(3) && keys.contains(2047));
+/// ```
+pub fn gen_galois_keys(sk: &SecretKey, elements: &[usize], base: u32, seed: Option<u64>) -> Result<GaloisKeys, RingLweError> {
+    sk.galois_keys(elements, base, seed)
+}
 
 /// Generate a public and secret key pair
 /// # Arguments:
-///	* `params` - ring-LWE parameters
+/// * `params` - ring-LWE parameters
 /// * `seed` - random seed
 /// # Returns:
-///	(public key, secret key)
+/// (public key, secret key)
 /// # Example:
 /// ```
 /// let params = ring_lwe::utils::Parameters::default();
 /// let (pk, sk) = ring_lwe::keygen::keygen(&params, None);
 /// ```
 pub fn keygen(params: &Parameters, seed: Option<u64>) -> ([Polynomial<i64>; 2], Polynomial<i64>) {
+    let (pk, sk) = SecretKey::generate(params, seed);
+    (pk.into_array(), sk.into_poly())
+}
 
-    //rename parameters
-    let (n, q, f, omega) = (params.n, params.q, &params.f, params.omega);
-
-    // Generate a public and secret key
-    let sk = gen_ternary_poly(n, seed);
-    let a = gen_uniform_poly(n, q, seed);
-    let e = gen_ternary_poly(n, seed);
-    let b = polyadd(&polymul_fast(&polyinv(&a,q), &sk, q, &f, omega), &polyinv(&e,q), q, &f); // b = -a*sk - e
-    
-    // Return public key (b, a) as an array and secret key (sk)
-    ([b, a], sk)
+/// Generate a public and secret key pair from a caller-supplied random number generator
+/// # Arguments:
+/// * `params` - ring-LWE parameters
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// (public key, secret key)
+pub fn keygen_with_rng<R: RngCore + CryptoRng>(params: &Parameters, rng: &mut R) -> ([Polynomial<i64>; 2], Polynomial<i64>) {
+    let (pk, sk) = SecretKey::generate_with_rng(params, rng);
+    (pk.into_array(), sk.into_poly())
 }
 
 /// Generate a public and secret key pair and return as a HashMap
 /// # Arguments:
-///	* `params` - ring-LWE parameters
+/// * `params` - ring-LWE parameters
 /// * `seed` - random seed
 /// # Returns:
-///	HashMap containing public and secret keys as base64 encoded strings
+/// HashMap containing public and secret keys as base64 encoded strings
 /// # Example:
 /// ```
 /// let params = ring_lwe::utils::Parameters::default();
@@ -42,16 +228,31 @@ pub fn keygen(params: &Parameters, seed: Option<u64>) -> ([Polynomial<i64>; 2],
 /// let sk_string = keys.get("secret").unwrap();
 /// ```
 pub fn keygen_string(params: &Parameters, seed: Option<u64>) -> HashMap<String, String> {
-    // Generate keys using parameters
-    let (pk, sk) = keygen(params, seed);
+    keygen_string_with_format(params, seed, WireFormat::Bincode)
+}
 
-    let mut pk_coeffs: Vec<i64> = Vec::with_capacity(2 * params.n);
-    pk_coeffs.extend(pk[0].coeffs());
-    pk_coeffs.extend(pk[1].coeffs());
+/// Generate a public and secret key pair and return as a HashMap, encoded in the given wire format
+/// # Arguments:
+/// * `params` - ring-LWE parameters
+/// * `seed` - random seed
+/// * `format` - wire format of the encoded keys
+/// # Returns:
+/// HashMap containing public and secret keys as base64 encoded strings
+/// # Example:
+/// ```
+/// use ring_lwe::utils::WireFormat;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let keys = ring_lwe::keygen::keygen_string_with_format(&params, None, WireFormat::Packed);
+/// let pk_string = keys.get("public").unwrap();
+/// assert!(pk_string.len() < 5000);
+/// ```
+pub fn keygen_string_with_format(params: &Parameters, seed: Option<u64>, format: WireFormat) -> HashMap<String, String> {
+    // Generate keys using parameters
+    let (pk, sk) = SecretKey::generate(params, seed);
 
     // Store public/secret keys in a HashMap
     let mut keys: HashMap<String, String> = HashMap::new();
-    keys.insert(String::from("secret"), compress(&sk.coeffs().to_vec()));
-    keys.insert(String::from("public"), compress(&pk_coeffs));
+    keys.insert(String::from("secret"), sk.encode(format));
+    keys.insert(String::from("public"), pk.encode(format));
     keys
 }
\ No newline at end of file
@@ -1,4 +1,15 @@
 pub mod keygen;
 pub mod encrypt;
 pub mod decrypt;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod types;
+pub mod error;
+pub mod reduction;
+pub mod format;
+pub mod codec;
+pub mod encoding;
+pub mod evaluate;
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+mod test;
\ No newline at end of file
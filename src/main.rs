@@ -1,15 +1,10 @@
-mod keygen;
-mod encrypt;
-mod decrypt;
-mod utils;
-mod test;
-
-use crate::keygen::keygen_string;
-use crate::encrypt::encrypt_string;
-use crate::decrypt::decrypt_string;
-use crate::utils::Parameters;
-use polynomial_ring::Polynomial;
-use clap::{Parser, Subcommand};
+use ring_lwe::keygen::keygen_string_with_format;
+use ring_lwe::encrypt::encrypt_string_with_format;
+use ring_lwe::decrypt::decrypt_string_with_format;
+use ring_lwe::utils::{Parameters, WireFormat};
+use ring_lwe::error::RingLweError;
+use ring_lwe::format::{armor, ObjectType};
+use clap::{ArgGroup, Parser, Subcommand};
 use std::fs;
 
 /// A simple Ring-LWE encryption tool
@@ -35,9 +30,18 @@ enum Commands {
         /// Optional flag to save keys to files
         #[arg(long)]
         save_keys: bool,
+
+        /// Optional flag to bit-pack the keys
+        #[arg(long)]
+        packed: bool,
+
+        /// Optional flag to write bare base64 keys instead of armored ones
+        #[arg(long)]
+        no_armor: bool,
     },
 
     /// Encrypt a message
+    #[command(group(ArgGroup::new("pubkey_source").required(true)))]
     Encrypt {
         /// Public key (base64 string)
         #[arg(long, group = "pubkey_source")]
@@ -61,9 +65,19 @@ enum Commands {
         q: Option<i64>,
         #[arg(long)]
         t: Option<i64>,
+
+        /// Optional flag for a bit-packed key and ciphertext
+        #[arg(long)]
+        packed: bool,
+
+        /// Optional flag to write a bare base64 ciphertext instead of an armored one
+        #[arg(long)]
+        no_armor: bool,
     },
 
     /// Decrypt a ciphertext
+    #[command(group(ArgGroup::new("seckey_source").required(true)))]
+    #[command(group(ArgGroup::new("ciphertext_source").required(true)))]
     Decrypt {
         /// Secret key (base64 string)
         #[arg(long, group = "seckey_source")]
@@ -74,6 +88,7 @@ enum Commands {
         secret_file: Option<String>,
 
         /// Ciphertext to decrypt
+        #[arg(group = "ciphertext_source")]
         ciphertext: Option<String>,
 
         /// Ciphertext file
@@ -87,37 +102,42 @@ enum Commands {
         q: Option<i64>,
         #[arg(long)]
         t: Option<i64>,
+
+        /// Optional flag for a bit-packed key and ciphertext
+        #[arg(long)]
+        packed: bool,
     },
 }
 
 fn main() {
     let cli = Cli::parse();
 
+    if let Err(err) = run(cli) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+/// Run the selected subcommand
+fn run(cli: Cli) -> Result<(), RingLweError> {
     match cli.command {
-        Commands::Keygen { n, q, t, save_keys } => {
-            let params = build_params(n, q, t);
-            let keypair = keygen_string(&params, None);
+        Commands::Keygen { n, q, t, save_keys, packed, no_armor } => {
+            let params = build_params(n, q, t)?;
+            let keypair = keygen_string_with_format(&params, None, wire_format(packed));
+            let public = output(ObjectType::PublicKey, &keypair["public"], no_armor);
+            let secret = output(ObjectType::SecretKey, &keypair["secret"], no_armor);
 
             if save_keys {
-                use std::fs::File;
-                use std::io::Write;
-
-                let public_key = keypair.get("public").expect("No public key found");
-                let secret_key = keypair.get("secret").expect("No secret key found");
-
-                let mut pk_file = File::create("public.key").expect("Failed to create public.key");
-                pk_file.write_all(public_key.as_bytes())
-                    .expect("Failed to write public key");
-
-                let mut sk_file = File::create("secret.key").expect("Failed to create secret.key");
-                sk_file.write_all(secret_key.as_bytes())
-                    .expect("Failed to write secret key");
-
+                fs::write("public.key", &public)?;
+                fs::write("secret.key", &secret)?;
                 println!("Keys saved to public.key and secret.key");
             }
-            else {
+            else if no_armor {
                 println!("{:?}", keypair);
             }
+            else {
+                print!("{}{}", public, secret);
+            }
         }
 
         Commands::Encrypt {
@@ -128,32 +148,26 @@ fn main() {
             n,
             q,
             t,
+            packed,
+            no_armor,
         } => {
-            let params = build_params(n, q, t);
-
-            // Load pubkey from inline arg or file
-            let pk_string = if let Some(pk) = pubkey {
-                pk
-            } else if let Some(file) = pubkey_file {
-                fs::read_to_string(file)
-                    .expect("Failed to read public key file")
-                    .trim()
-                    .to_string()
-            } else {
-                panic!("Must supply either --pubkey or --pubkey-file");
+            let params = build_params(n, q, t)?;
+
+            // Load pubkey from inline arg or file (clap guarantees exactly one)
+            let pk_string = match (pubkey, pubkey_file) {
+                (Some(pk), _) => pk,
+                (None, Some(file)) => read_trimmed(&file)?,
+                (None, None) => unreachable!("clap requires --pubkey or --pubkey-file"),
             };
 
-            let ciphertext = encrypt_string(&pk_string, &message, &params, None);
-            
+            let ciphertext = encrypt_string_with_format(&pk_string, &message, &params, None, wire_format(packed))?;
+            let ciphertext = output(ObjectType::Ciphertext, &ciphertext, no_armor);
+
             if let Some(file) = ciphertext_file {
-                use std::fs::File;
-                use std::io::Write;
-                let mut f = File::create(&file).expect("Failed to create ciphertext file");
-                f.write_all(ciphertext.as_bytes())
-                    .expect("Failed to write ciphertext");
+                fs::write(&file, &ciphertext)?;
                 println!("Ciphertext saved to {}", file);
             } else {
-                println!("{}", ciphertext);
+                print!("{}", ciphertext);
             }
         }
 
@@ -165,52 +179,58 @@ fn main() {
             n,
             q,
             t,
+            packed,
         } => {
-            let params = build_params(n, q, t);
-
-            // Load secret key from inline arg or file
-            let sk_string = if let Some(sk) = secret {
-                sk
-            } else if let Some(file) = secret_file {
-                fs::read_to_string(file)
-                    .expect("Failed to read secret key file")
-                    .trim()
-                    .to_string()
-            } else {
-                panic!("Must supply either --secret or --secret-file");
+            let params = build_params(n, q, t)?;
+
+            // Load secret key from inline arg or file (clap guarantees exactly one)
+            let sk_string = match (secret, secret_file) {
+                (Some(sk), _) => sk,
+                (None, Some(file)) => read_trimmed(&file)?,
+                (None, None) => unreachable!("clap requires --secret or --secret-file"),
             };
 
-            // Load ciphertext from inline arg or file
-            let ct_string = if let Some(file) = ciphertext_file {
-                fs::read_to_string(file)
-                    .expect("Failed to read ciphertext file")
-                    .trim()
-                    .to_string()
-            } else if let Some(ct) = ciphertext {
-                ct.clone()
-            } else {
-                panic!("Must supply either ciphertext or --ciphertext-file");
+            // Load ciphertext from inline arg or file (clap guarantees exactly one)
+            let ct_string = match (ciphertext, ciphertext_file) {
+                (Some(ct), _) => ct,
+                (None, Some(file)) => read_trimmed(&file)?,
+                (None, None) => unreachable!("clap requires a ciphertext or --ciphertext-file"),
             };
 
-            let message = decrypt_string(&sk_string, &ct_string, &params);
+            let message = decrypt_string_with_format(&sk_string, &ct_string, &params, wire_format(packed))?;
             println!("{:?}", message);
         }
     }
+
+    Ok(())
 }
 
-/// Helper to build parameters with default fallback
-fn build_params(n: Option<usize>, q: Option<i64>, t: Option<i64>) -> Parameters {
-    let mut params = Parameters::default();
-
-    if let (Some(n), Some(q), Some(t)) = (n, q, t) {
-        params.n = n;
-        params.q = q;
-        params.t = t;
-        let mut poly_vec = vec![0i64; params.n + 1];
-        poly_vec[0] = 1;
-        poly_vec[params.n] = 1;
-        params.f = Polynomial::new(poly_vec);
-    }
+/// Armor a key or ciphertext unless --no-armor was given; either way ending in a newline
+fn output(object_type: ObjectType, base64_str: &str, no_armor: bool) -> String {
+    if no_armor { format!("{}\n", base64_str) } else { armor(object_type, base64_str) }
+}
 
-    params
+/// Read a key or ciphertext file, armored or not, stripping surrounding whitespace
+fn read_trimmed(path: &str) -> Result<String, RingLweError> {
+    Ok(fs::read_to_string(path)?.trim().to_string())
+}
+
+/// Wire format selected by the --packed flag
+fn wire_format(packed: bool) -> WireFormat {
+    if packed { WireFormat::Packed } else { WireFormat::Bincode }
+}
+
+/// Helper to build checked parameters, falling back to the defaults for omitted values
+fn build_params(n: Option<usize>, q: Option<i64>, t: Option<i64>) -> Result<Parameters, RingLweError> {
+    let mut builder = Parameters::builder();
+    if let Some(n) = n {
+        builder = builder.n(n);
+    }
+    if let Some(q) = q {
+        builder = builder.q(q);
+    }
+    if let Some(t) = t {
+        builder = builder.t(t);
+    }
+    builder.build()
 }
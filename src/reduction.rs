@@ -0,0 +1,288 @@
+/// Inverse of an odd number modulo 2^16 by Newton iteration
+fn inv_mod_2_16(q: u16) -> u16 {
+    // q*q = 1 mod 8, and each step doubles the number of correct low bits
+    let mut inv = q;
+    for _ in 0..3 {
+        inv = inv.wrapping_mul(2u16.wrapping_sub(q.wrapping_mul(inv)));
+    }
+    inv
+}
+
+/// Inverse of an odd number modulo 2^32 by Newton iteration
+fn inv_mod_2_32(q: u32) -> u32 {
+    let mut inv = q;
+    for _ in 0..4 {
+        inv = inv.wrapping_mul(2u32.wrapping_sub(q.wrapping_mul(inv)));
+    }
+    inv
+}
+
+/// Montgomery reduction with R = 2^16 for odd moduli q < 2^15
+/// # Example:
+/// ```
+/// let mont = ring_lwe::reduction::Montgomery16::new(3329);
+/// let (a, b) = (mont.to_montgomery(1234), mont.to_montgomery(2345));
+/// assert_eq!(mont.from_montgomery(mont.mul(a, b)) as u32, 1234 * 2345 % 3329);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Montgomery16 {
+    q: u16,
+    q_neg_inv: u16, // -q^-1 mod 2^16
+    r2: u16,        // 2^32 mod q
+}
+
+impl Montgomery16 {
+    /// Precompute the constants for a modulus
+    /// # Arguments:
+    /// * `q` - odd modulus below 2^15
+    /// # Panics:
+    /// if q is even or too large
+    pub fn new(q: u16) -> Self {
+        assert!(q % 2 == 1 && q < 1 << 15, "Montgomery16 needs an odd modulus below 2^15");
+        let r2 = ((1u64 << 32) % q as u64) as u16;
+        Montgomery16 { q, q_neg_inv: inv_mod_2_16(q).wrapping_neg(), r2 }
+    }
+
+    /// Modulus q
+    pub fn modulus(&self) -> u16 {
+        self.q
+    }
+
+    /// Compute a * 2^-16 mod q
+    /// # Arguments:
+    /// * `a` - integer below q * 2^16
+    /// # Returns:
+    /// a * 2^-16 mod q in [0, q)
+    pub fn reduce(&self, a: u32) -> u16 {
+        let m = (a as u16).wrapping_mul(self.q_neg_inv);
+        // a + m*q is divisible by 2^16 and below 2^32
+        let t = ((a + m as u32 * self.q as u32) >> 16) as u16;
+        if t >= self.q { t - self.q } else { t }
+    }
+
+    /// Product of two values in Montgomery form, in Montgomery form
+    pub fn mul(&self, a: u16, b: u16) -> u16 {
+        self.reduce(a as u32 * b as u32)
+    }
+
+    /// Convert a value in [0, q) to Montgomery form a * 2^16 mod q
+    pub fn to_montgomery(&self, a: u16) -> u16 {
+        self.mul(a, self.r2)
+    }
+
+    /// Convert a value out of Montgomery form
+    pub fn from_montgomery(&self, a: u16) -> u16 {
+        self.reduce(a as u32)
+    }
+}
+
+/// Montgomery reduction with R = 2^32 for odd moduli q < 2^31
+/// # Example:
+/// ```
+/// let mont = ring_lwe::reduction::Montgomery32::new(12289);
+/// let (a, b) = (mont.to_montgomery(1234), mont.to_montgomery(5678));
+/// assert_eq!(mont.from_montgomery(mont.mul(a, b)), 1234 * 5678 % 12289);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Montgomery32 {
+    q: u32,
+    q_neg_inv: u32, // -q^-1 mod 2^32
+    r2: u32,        // 2^64 mod q
+}
+
+impl Montgomery32 {
+    /// Precompute the constants for a modulus
+    /// # Arguments:
+    /// * `q` - odd modulus below 2^31
+    /// # Panics:
+    /// if q is even or too large
+    pub fn new(q: u32) -> Self {
+        assert!(q % 2 == 1 && q < 1 << 31, "Montgomery32 needs an odd modulus below 2^31");
+        let r2 = ((1u128 << 64) % q as u128) as u32;
+        Montgomery32 { q, q_neg_inv: inv_mod_2_32(q).wrapping_neg(), r2 }
+    }
+
+    /// Modulus q
+    pub fn modulus(&self) -> u32 {
+        self.q
+    }
+
+    /// Compute a * 2^-32 mod q
+    /// # Arguments:
+    /// * `a` - integer below q * 2^32
+    /// # Returns:
+    /// a * 2^-32 mod q in [0, q)
+    pub fn reduce(&self, a: u64) -> u32 {
+        let m = (a as u32).wrapping_mul(self.q_neg_inv);
+        // a + m*q is divisible by 2^32 and below 2^64
+        let t = ((a + m as u64 * self.q as u64) >> 32) as u32;
+        if t >= self.q { t - self.q } else { t }
+    }
+
+    /// Product of two values in Montgomery form, in Montgomery form
+    pub fn mul(&self, a: u32, b: u32) -> u32 {
+        self.reduce(a as u64 * b as u64)
+    }
+
+    /// Convert a value in [0, q) to Montgomery form a * 2^32 mod q
+    pub fn to_montgomery(&self, a: u32) -> u32 {
+        self.mul(a, self.r2)
+    }
+
+    /// Convert a value out of Montgomery form
+    pub fn from_montgomery(&self, a: u32) -> u32 {
+        self.reduce(a as u64)
+    }
+}
+
+/// Barrett reduction of 32-bit integers for moduli 2 <= q < 2^16
+/// # Example:
+/// ```
+/// let barrett = ring_lwe::reduction::Barrett16::new(3329);
+/// assert_eq!(barrett.reduce(4_000_000_000), (4_000_000_000u32 % 3329) as u16);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Barrett16 {
+    q: u16,
+    m: u32, // floor(2^32 / q)
+}
+
+impl Barrett16 {
+    /// Precompute the constants for a modulus
+    /// # Arguments:
+    /// * `q` - modulus, at least 2
+    /// # Panics:
+    /// if q < 2
+    pub fn new(q: u16) -> Self {
+        assert!(q >= 2, "Barrett16 needs a modulus of at least 2");
+        Barrett16 { q, m: ((1u64 << 32) / q as u64) as u32 }
+    }
+
+    /// Modulus q
+    pub fn modulus(&self) -> u16 {
+        self.q
+    }
+
+    /// Compute a mod q
+    /// # Arguments:
+    /// * `a` - any 32-bit integer
+    /// # Returns:
+    /// a mod q in [0, q)
+    pub fn reduce(&self, a: u32) -> u16 {
+        // the quotient estimate is floor(a/q) or one less
+        let quot = ((a as u64 * self.m as u64) >> 32) as u32;
+        let r = a - quot * self.q as u32;
+        (if r >= self.q as u32 { r - self.q as u32 } else { r }) as u16
+    }
+
+    /// Compute the centered residue of a signed integer
+    /// # Arguments:
+    /// * `a` - any 32-bit integer
+    /// # Returns:
+    /// a mod q in (-q/2, q/2]
+    pub fn reduce_centered(&self, a: i32) -> i32 {
+        let q = self.q as i32;
+        let mut r = self.reduce(a.unsigned_abs()) as i32;
+        if a < 0 && r != 0 {
+            r = q - r;
+        }
+        if r > q / 2 { r - q } else { r }
+    }
+}
+
+/// Barrett reduction of 64-bit integers for moduli 2 <= q < 2^32
+/// # Example:
+/// ```
+/// let barrett = ring_lwe::reduction::Barrett32::new(12289);
+/// assert_eq!(barrett.reduce(u64::MAX), (u64::MAX % 12289) as u32);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Barrett32 {
+    q: u32,
+    m: u64, // floor(2^64 / q)
+}
+
+impl Barrett32 {
+    /// Precompute the constants for a modulus
+    /// # Arguments:
+    /// * `q` - modulus, at least 2
+    /// # Panics:
+    /// if q < 2
+    pub fn new(q: u32) -> Self {
+        assert!(q >= 2, "Barrett32 needs a modulus of at least 2");
+        Barrett32 { q, m: ((1u128 << 64) / q as u128) as u64 }
+    }
+
+    /// Modulus q
+    pub fn modulus(&self) -> u32 {
+        self.q
+    }
+
+    /// Compute a mod q
+    /// # Arguments:
+    /// * `a` - any 64-bit integer
+    /// # Returns:
+    /// a mod q in [0, q)
+    pub fn reduce(&self, a: u64) -> u32 {
+        // the quotient estimate is floor(a/q) or one less
+        let quot = ((a as u128 * self.m as u128) >> 64) as u64;
+        let r = a - quot * self.q as u64;
+        (if r >= self.q as u64 { r - self.q as u64 } else { r }) as u32
+    }
+
+    /// Compute the centered residue of a signed integer
+    /// # Arguments:
+    /// * `a` - any 64-bit integer
+    /// # Returns:
+    /// a mod q in (-q/2, q/2]
+    pub fn reduce_centered(&self, a: i64) -> i64 {
+        let q = self.q as i64;
+        let mut r = self.reduce(a.unsigned_abs()) as i64;
+        if a < 0 && r != 0 {
+            r = q - r;
+        }
+        if r > q / 2 { r - q } else { r }
+    }
+}
+
+/// Montgomery and Barrett reducers for an odd modulus, on 16-bit words when q < 2^15
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reducer {
+    Word16(Montgomery16, Barrett16),
+    Word32(Montgomery32, Barrett32),
+}
+
+impl Reducer {
+    /// Pick the reducers for an odd modulus q < 2^31
+    pub(crate) fn new(q: u32) -> Self {
+        match u16::try_from(q) {
+            Ok(q) if q < 1 << 15 => Reducer::Word16(Montgomery16::new(q), Barrett16::new(q)),
+            _ => Reducer::Word32(Montgomery32::new(q), Barrett32::new(q)),
+        }
+    }
+
+    /// Product of two values in Montgomery form, in Montgomery form
+    pub(crate) fn mont_mul(&self, a: u32, b: u32) -> u32 {
+        match self {
+            Reducer::Word16(mont, _) => mont.mul(a as u16, b as u16) as u32,
+            Reducer::Word32(mont, _) => mont.mul(a, b),
+        }
+    }
+
+    /// Convert a value in [0, q) to Montgomery form
+    pub(crate) fn montgomery_form(&self, a: u32) -> u32 {
+        match self {
+            Reducer::Word16(mont, _) => mont.to_montgomery(a as u16) as u32,
+            Reducer::Word32(mont, _) => mont.to_montgomery(a),
+        }
+    }
+
+    /// Product a * b mod q of two values in [0, q), by Barrett reduction
+    pub(crate) fn mul_mod(&self, a: u32, b: u32) -> u32 {
+        match self {
+            // a * b < 2^30 fits the 32-bit input of Barrett16
+            Reducer::Word16(_, barrett) => barrett.reduce(a * b) as u32,
+            Reducer::Word32(_, barrett) => barrett.reduce(a as u64 * b as u64),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Serde support for parameters, keys and ciphertexts, enabled by the `serde` feature
+//!
+//! Parameters serialize as (n, q, t, sigma, error_dist, modulus_chain) and are rebuilt through
+//! `ParametersBuilder`, so deserialized parameters are validated and get fresh NTT tables.
+//! Keys and ciphertexts serialize as their parameters plus the bit-packed coefficients,
+//! base64 encoded for human-readable formats such as JSON and raw bytes otherwise.
+
+use crate::utils::{Parameters, ErrorDistribution, encode_bytes, decode_bytes};
+use crate::types::{PublicKey, SecretKey, Ciphertext};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+/// Serialized form of `Parameters`; omega, f and the NTT tables are derived on load
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Parameters")]
+struct ParametersRepr {
+    n: usize,
+    q: i64,
+    t: i64,
+    sigma: f64,
+    error_dist: ErrorDistribution,
+    #[serde(default)]
+    modulus_chain: Vec<i64>,
+}
+
+impl Serialize for Parameters {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ParametersRepr {
+            n: self.n,
+            q: self.q,
+            t: self.t,
+            sigma: self.sigma,
+            error_dist: self.error_dist,
+            modulus_chain: self.modulus_chain.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Parameters {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ParametersRepr::deserialize(deserializer)?;
+        Parameters::builder()
+            .n(repr.n)
+            .q(repr.q)
+            .t(repr.t)
+            .sigma(repr.sigma)
+            .error_distribution(repr.error_dist)
+            .modulus_chain(&repr.modulus_chain)
+            .build()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Packed coefficients: a base64 string in human-readable formats, raw bytes otherwise
+struct PackedBytes(Vec<u8>);
+
+impl Serialize for PackedBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&encode_bytes(&self.0))
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PackedBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(PackedBytesVisitor)
+        } else {
+            deserializer.deserialize_byte_buf(PackedBytesVisitor)
+        }
+    }
+}
+
+struct PackedBytesVisitor;
+
+impl<'de> Visitor<'de> for PackedBytesVisitor {
+    type Value = PackedBytes;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("packed coefficients as a base64 string or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<PackedBytes, E> {
+        decode_bytes(v).map(PackedBytes).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<PackedBytes, E> {
+        Ok(PackedBytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<PackedBytes, E> {
+        Ok(PackedBytes(v))
+    }
+
+    // formats without a native bytes type hand them over as a sequence
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<PackedBytes, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(PackedBytes(bytes))
+    }
+}
+
+/// Serialized form shared by keys and ciphertexts
+#[derive(Serialize, Deserialize)]
+struct PackedRepr {
+    params: Parameters,
+    packed: PackedBytes,
+}
+
+/// Implement serde through `to_packed` and `from_packed` for a key or ciphertext type
+macro_rules! impl_packed_serde {
+    ($($ty:ty),*) => {$(
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                PackedRepr { params: self.params().clone(), packed: PackedBytes(self.to_packed()) }
+                    .serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let repr = PackedRepr::deserialize(deserializer)?;
+                <$ty>::from_packed(&repr.packed.0, &repr.params).map_err(de::Error::custom)
+            }
+        }
+    )*};
+}
+
+impl_packed_serde!(PublicKey, SecretKey, Ciphertext);
@@ -1,9 +1,20 @@
 #[cfg(test)]  // This makes the following module compile only during tests
 mod tests {
-    use crate::keygen::{keygen, keygen_string};
-    use crate::encrypt::{encrypt, encrypt_string};
-    use crate::decrypt::{decrypt, decrypt_string};
-    use crate::utils::{Parameters, polyadd, polymul, polymul_fast, mod_coeffs, nearest_int, gen_uniform_poly};
+    use crate::keygen::{keygen, keygen_string, keygen_string_with_format, keygen_with_rng, gen_galois_keys};
+    use crate::encrypt::{encrypt, encrypt_bytes, encrypt_string, encrypt_string_with_format, encrypt_with_rng, compress_ciphertext, compression_failure_probability};
+    use crate::decrypt::{decrypt, decrypt_bytes, noise_budget, decrypt_string, decrypt_string_with_format, decompress_ciphertext};
+    use crate::types::{PublicKey, SecretKey, Ciphertext, CompressedCiphertext, RelinKey};
+    use crate::error::RingLweError;
+    use crate::reduction::{Barrett16, Barrett32, Montgomery16, Montgomery32};
+    use crate::codec::{bits_per_coeff, encode_message, decode_message, LENGTH_HEADER_BYTES};
+    use crate::encoding::{IntegerEncoder, CoeffEncoder, SignedEncoder, BatchEncoder};
+    use crate::evaluate::{add, sub, negate, multiply, relinearize, add_plain, sub_plain, multiply_plain};
+    use crate::evaluate::{apply_galois, rotate_rows, rotate_columns, row_rotation_element, column_rotation_element, mod_switch, mod_switch_to_next};
+    use std::borrow::Cow;
+    use crate::format::{armor, dearmor, decode_container, encode_container, Header, ObjectType, MAGIC, FORMAT_VERSION};
+    use crate::utils::{polymul_negacyclic, ntt_negacyclic, intt_negacyclic, NttContext, NttPoly, RingPoly, polysub, WireFormat, pack_coeffs, unpack_coeffs, coeff_bits, compress_d, decompress_d, encode_bytes, decode_bytes, deserialize_coeffs};
+    use crate::utils::{compress, is_prime, ErrorDistribution, gen_error_poly, gen_cbd_poly, gen_discrete_gaussian_poly};
+    use crate::utils::{Parameters, polyadd, polymul, polymul_fast, polyinv, mod_coeffs, nearest_int, gen_uniform_poly, expand_seed, SeedDomain};
     use ntt::omega;
     use polynomial_ring::Polynomial;
 
@@ -16,8 +27,8 @@ mod tests {
         let keypair = keygen_string(&params,seed);
         let pk_string = keypair.get("public").unwrap();
         let sk_string = keypair.get("secret").unwrap();
-        let ciphertext_string = encrypt_string(&pk_string, &message, &params,seed);
-        let decrypted_message = decrypt_string(&sk_string, &ciphertext_string, &params);
+        let ciphertext_string = encrypt_string(pk_string, &message, &params,seed).unwrap();
+        let decrypted_message = decrypt_string(sk_string, &ciphertext_string, &params).unwrap();
         assert_eq!(message, decrypted_message, "test failed: {} != {}", message, decrypted_message);
     }
 
@@ -33,7 +44,7 @@ mod tests {
         let m0_poly = Polynomial::new(vec![1, 0, 1]);
         let m1_poly = Polynomial::new(vec![0, 0, 1]);
 
-        let plaintext_sum = polyadd(&m0_poly, &m1_poly, t, &f);
+        let plaintext_sum = polyadd(&m0_poly, &m1_poly, t, f);
         let (pk, sk) = keygen(&params,seed);
 
         // Encrypt plaintext messages
@@ -70,17 +81,17 @@ mod tests {
         let u = encrypt(&pk, &m0_poly, &params, seed);
         let v = encrypt(&pk, &m1_poly, &params, seed);
 
-        let plaintext_prod = polymul(&m0_poly, &m1_poly, t, &f);
+        let plaintext_prod = polymul(&m0_poly, &m1_poly, t, f);
         //compute product of encrypted data, using non-standard multiplication
-        let c0 = polymul(&u[0],&v[0],params.q,&f);
-        let u0v1 = &polymul(&u[0],&v[1],params.q,&f);
-        let u1v0 = &polymul(&u[1],&v[0],params.q,&f);
-        let c1 = polyadd(u0v1,u1v0,params.q,&f);
-        let c2 = polymul(&u[1],&v[1],params.q,&f);
+        let c0 = polymul(&u[0],&v[0],params.q,f);
+        let u0v1 = &polymul(&u[0],&v[1],params.q,f);
+        let u1v0 = &polymul(&u[1],&v[0],params.q,f);
+        let c1 = polyadd(u0v1,u1v0,params.q,f);
+        let c2 = polymul(&u[1],&v[1],params.q,f);
         //compute c0 + c1*s + c2*s*s
-        let c1_sk = &polymul(&c1,&sk,params.q,&f);
-        let c2_sk_squared = &polymul(&polymul(&c2,&sk,params.q,&f),&sk,params.q,&f);
-        let ciphertext_prod = polyadd(&polyadd(&c0,c1_sk,params.q,&f),c2_sk_squared,params.q,&f);
+        let c1_sk = &polymul(&c1,&sk,params.q,f);
+        let c2_sk_squared = &polymul(&polymul(&c2,&sk,params.q,f),&sk,params.q,f);
+        let ciphertext_prod = polyadd(&polyadd(&c0,c1_sk,params.q,f),c2_sk_squared,params.q,f);
         //let delta = q / t, divide coeffs by 1 / delta^2
         let delta = q / t;
         let decrypted_prod = mod_coeffs(Polynomial::new(ciphertext_prod.coeffs().iter().map(|&coeff| nearest_int(coeff,delta * delta) ).collect::<Vec<_>>()),t);
@@ -121,4 +132,944 @@ mod tests {
 
         assert_eq!(c_std, c_fast, "test failed: {} != {}", c_std, c_fast);
     }
-}
\ No newline at end of file
+
+    // Test that a fixed seed gives reproducible keys and ciphertexts which still decrypt
+    #[test]
+    pub fn test_seeded_reproducible() {
+        let seed = Some(42);
+        let params = Parameters::default();
+        let m = Polynomial::new(vec![1, 0, 1, 1]);
+
+        let (pk0, sk0) = keygen(&params, seed);
+        let (pk1, sk1) = keygen(&params, seed);
+        assert_eq!(pk0, pk1);
+        assert_eq!(sk0, sk1);
+
+        let ct0 = encrypt(&pk0, &m, &params, seed);
+        let ct1 = encrypt(&pk1, &m, &params, seed);
+        assert_eq!(ct0, ct1);
+        assert_eq!(decrypt(&sk0, &ct0, &params), m);
+    }
+
+    // Test that the secret and error in keygen come from independent streams
+    #[test]
+    pub fn test_seeded_independent_streams() {
+        let seed = Some(7);
+        let params = Parameters::default();
+        let (q, f) = (params.q, &params.f);
+        let (pk, sk) = keygen(&params, seed);
+
+        // recover e = -(b + a*sk) from b = -a*sk - e
+        let e = polyinv(&polyadd(&pk[0], &polymul(&pk[1], &sk, q, f), q, f), q);
+        assert_ne!(sk, e, "secret and error polynomials must differ");
+
+        let domains = [SeedDomain::Secret, SeedDomain::Error, SeedDomain::Uniform, SeedDomain::Ephemeral,
+            SeedDomain::Error1, SeedDomain::Error2, SeedDomain::Block(0), SeedDomain::Block(1),
+            SeedDomain::RelinKey, SeedDomain::GaloisKey(3), SeedDomain::KeySwitchUniform(0), SeedDomain::KeySwitchError(0)];
+        for (i, d0) in domains.iter().enumerate() {
+            for d1 in &domains[i+1..] {
+                assert_ne!(expand_seed(seed, *d0), expand_seed(seed, *d1), "{:?} and {:?} collide", d0, d1);
+            }
+        }
+    }
+
+    // Test the typed key and ciphertext API agrees with the array-based free functions
+    #[test]
+    pub fn test_typed_api() {
+        let seed = Some(3);
+        let params = Parameters::default();
+        let m = Polynomial::new(vec![0, 1, 1, 0, 1]);
+
+        let (pk, sk) = SecretKey::generate(&params, seed);
+        let ct = pk.encrypt(&m, seed);
+        assert_eq!(ct.params(), &params);
+        assert_eq!(sk.decrypt(&ct).unwrap(), m);
+
+        let (pk_arr, sk_poly) = keygen(&params, seed);
+        assert_eq!(pk_arr, [pk.b().to_poly(), pk.a().to_poly()]);
+        assert_eq!(sk_poly, sk.s().to_poly());
+        assert_eq!(encrypt(&pk_arr, &m, &params, seed), ct.into_array());
+    }
+
+    // Test keys and ciphertexts holding their coefficients in u16 words
+    #[test]
+    pub fn test_u16_words() {
+        let seed = Some(5);
+        let params = Parameters::default();
+        let m = Polynomial::new(vec![1, 0, 1, 1]);
+
+        let (pk, sk) = SecretKey::generate(&params, seed);
+        let (pk16, sk16): (PublicKey<u16>, SecretKey<u16>) = (pk.to_word(), sk.to_word());
+        assert_eq!(sk16.s().coeffs().len(), params.n);
+        let ct16 = pk16.encrypt(&m, seed);
+        assert_eq!(ct16.to_coeffs(), pk.encrypt(&m, seed).to_coeffs());
+        assert_eq!(sk16.decrypt(&ct16).unwrap(), m);
+        assert_eq!(sk16.decrypt(&add_plain(&ct16, &Polynomial::new(vec![1]))).unwrap(), Polynomial::new(vec![0, 0, 1, 1]));
+
+        // serialized forms are word independent
+        let loaded: Ciphertext<u16> = Ciphertext::from_packed(&ct16.to_packed(), &params).unwrap().to_word();
+        assert_eq!(loaded, ct16);
+        assert_eq!(SecretKey::from_packed(&sk16.to_packed(), &params).unwrap(), sk);
+        assert_eq!(sk16.decrypt(&loaded).unwrap(), m);
+    }
+
+    // Test that malformed keys and ciphertexts are reported as errors instead of panicking
+    #[test]
+    pub fn test_malformed_input_errors() {
+        let params = Parameters::default();
+        let keypair = keygen_string(&params, None);
+        let pk_string = keypair.get("public").unwrap();
+        let sk_string = keypair.get("secret").unwrap();
+
+        // invalid base64 and truncated bincode payload
+        assert!(matches!(encrypt_string("not base64!", "hi", &params, None), Err(RingLweError::Decode(_))));
+        assert!(matches!(decrypt_string(sk_string, "AAAA", &params), Err(RingLweError::Decode(_))));
+
+        // secret key passed where a public key is expected, with and without a container
+        assert!(matches!(encrypt_string(sk_string, "hi", &params, None), Err(RingLweError::ParameterMismatch(_))));
+        let sk_bare = SecretKey::decode(sk_string, &params, WireFormat::Bincode).unwrap().to_base64();
+        assert!(matches!(encrypt_string(&sk_bare, "hi", &params, None),
+            Err(RingLweError::LengthMismatch { expected: 2048, found: 1024 })));
+
+        // coefficient outside [-q/2, q/2]
+        let mut coeffs = vec![0i64; 2 * params.n];
+        coeffs[5] = params.q;
+        assert!(matches!(encrypt_string(&compress(&coeffs), "hi", &params, None),
+            Err(RingLweError::InvalidCoefficient { index: 5, .. })));
+
+        // ciphertext that is not a whole number of blocks
+        let ct_string = encrypt_string(pk_string, "hi", &params, None).unwrap();
+        let ct_bytes = crate::utils::decode_bytes(&ct_string).unwrap();
+        let mut ct_coeffs = crate::utils::deserialize_coeffs(crate::format::decode_container(&ct_bytes).unwrap().1).unwrap();
+        ct_coeffs.pop();
+        assert!(matches!(decrypt_string(sk_string, &compress(&ct_coeffs), &params),
+            Err(RingLweError::LengthMismatch { .. })));
+
+        // ciphertext under different parameters than the secret key
+        let (pk, sk) = SecretKey::generate(&params, None);
+        let mut other = params.clone();
+        other.t = 4;
+        let other_pk = PublicKey::from_ring(pk.b().clone(), pk.a().clone(), &other);
+        let ct = other_pk.encrypt(&Polynomial::new(vec![1]), None);
+        assert!(matches!(sk.decrypt(&ct), Err(RingLweError::ParameterMismatch(_))));
+    }
+
+    // Test that the parameters builder derives omega and f and rejects unusable combinations
+    #[test]
+    pub fn test_parameters_builder() {
+        assert_eq!(Parameters::builder().build().unwrap(), Parameters::default());
+
+        // omega is recomputed for the new degree and has order exactly 2n
+        let params = Parameters::builder().n(512).build().unwrap();
+        assert_eq!(params.omega, omega(params.q, 2*params.n));
+        assert_eq!(ntt::mod_exp(params.omega, params.n as i64, params.q), params.q - 1);
+        assert_eq!(params.f.coeffs().len(), 513);
+
+        let invalid = [
+            Parameters::builder().n(1000).build(),   // n not a power of two
+            Parameters::builder().q(12287).build(),  // q not prime
+            Parameters::builder().q(13).build(),     // q != 1 mod 2n
+            Parameters::builder().q(4293918721).build(), // q >= 2^31
+            Parameters::builder().t(12289).build(),  // t >= q
+            Parameters::builder().sigma(0.0).build(),
+            Parameters::builder().sigma(2.0e5).error_distribution(ErrorDistribution::DiscreteGaussian { tail_cut: 6.0 }).build(),
+            Parameters::builder().error_distribution(ErrorDistribution::DiscreteGaussian { tail_cut: 1.0e9 }).build(),
+            Parameters::builder().error_distribution(ErrorDistribution::DiscreteGaussian { tail_cut: f64::INFINITY }).build(),
+        ];
+        for result in invalid {
+            assert!(matches!(result, Err(RingLweError::InvalidParameters(_))));
+        }
+
+        assert!(is_prime(12289) && is_prime(3329) && is_prime(2305843009213693951));
+        assert!(!is_prime(1) && !is_prime(12289 * 12289) && !is_prime(3215031751));
+    }
+
+    // Test that keygen/encrypt/decrypt round-trip under every error distribution
+    #[test]
+    pub fn test_error_distributions() {
+        let dists = [
+            ErrorDistribution::Ternary,
+            ErrorDistribution::RoundedGaussian,
+            ErrorDistribution::DiscreteGaussian { tail_cut: 6.0 },
+            ErrorDistribution::CenteredBinomial { eta: 8 },
+        ];
+        let m = Polynomial::new(vec![1, 1, 0, 1, 0, 0, 1]);
+        for dist in dists {
+            let params = Parameters::builder().sigma(3.2).error_distribution(dist).build().unwrap();
+            let (pk, sk) = SecretKey::generate(&params, Some(11));
+            let ct = pk.encrypt(&m, Some(12));
+            assert_eq!(sk.decrypt(&ct).unwrap(), m, "decryption failed for {:?}", dist);
+        }
+    }
+
+    // Test that the sampled error polynomials have the expected support and variance
+    #[test]
+    pub fn test_error_distribution_statistics() {
+        let variance = |params: &Parameters| {
+            let e = gen_error_poly(params, Some(5));
+            e.coeffs().iter().map(|&c| (c * c) as f64).sum::<f64>() / params.n as f64
+        };
+        let builder = Parameters::builder().n(4096).q(40961).sigma(3.2);
+
+        let params = builder.clone().error_distribution(ErrorDistribution::CenteredBinomial { eta: 8 }).build().unwrap();
+        assert!((variance(&params) - 4.0).abs() < 0.5, "CBD variance should be eta/2");
+
+        let params = builder.clone().error_distribution(ErrorDistribution::RoundedGaussian).build().unwrap();
+        assert!((variance(&params) - 3.2 * 3.2).abs() < 1.5, "rounded Gaussian variance should be close to sigma^2");
+
+        let params = builder.error_distribution(ErrorDistribution::DiscreteGaussian { tail_cut: 2.0 }).build().unwrap();
+        let e = gen_error_poly(&params, Some(6));
+        assert!(e.coeffs().iter().all(|c| c.abs() <= 7), "discrete Gaussian must respect the tail cut");
+
+        // the cached table samples like a freshly built one, and is rebuilt once sigma changes
+        assert!(matches!(params.discrete_gaussian(), Some(Cow::Borrowed(_))));
+        assert_eq!(e, gen_discrete_gaussian_poly(params.n, params.sigma, 2.0, Some(6)));
+        let mut changed = params.clone();
+        changed.sigma = 1.0;
+        assert_eq!(changed.discrete_gaussian().unwrap().bound(), 2);
+    }
+
+    // Test the centered binomial sampler against the Binomial(4, 1/2) - 2 probabilities for eta = 2
+    #[test]
+    pub fn test_cbd_poly() {
+        let size = 16384;
+        let e = gen_cbd_poly(size, 2, Some(9));
+        assert_eq!(e, gen_cbd_poly(size, 2, Some(9)));
+
+        let mut counts = [0usize; 5];
+        for i in 0..size {
+            let c = e.coeffs().get(i).copied().unwrap_or(0);
+            counts[(c + 2) as usize] += 1;
+        }
+        for (count, weight) in counts.iter().zip([1, 4, 6, 4, 1]) {
+            let expected = (size * weight / 16) as f64;
+            assert!((*count as f64 - expected).abs() < 0.1 * expected, "{:?} deviates from binomial", counts);
+        }
+
+        // NewHope-style errors (eta = 8) on the default ring still decrypt
+        let params = Parameters::builder().error_distribution(ErrorDistribution::CenteredBinomial { eta: 8 }).build().unwrap();
+        let (pk, sk) = SecretKey::generate(&params, None);
+        let m = Polynomial::new(vec![1, 0, 1, 1]);
+        assert_eq!(sk.decrypt(&pk.encrypt(&m, None)).unwrap(), m);
+    }
+
+    // Test the integer-only discrete Gaussian sampler against the exact probabilities with a chi-square test
+    #[test]
+    pub fn test_discrete_gaussian_statistics() {
+        let (sigma, tail_cut, size) = (3.2, 6.0, 200_000);
+        let bound = 20;
+        let e = gen_discrete_gaussian_poly(size, sigma, tail_cut, Some(17));
+        let mut samples = e.coeffs().to_vec();
+        samples.resize(size, 0);
+        assert!(samples.iter().all(|c| c.abs() <= bound));
+
+        // expected probabilities rho(x) / sum rho over [-bound, bound]
+        let rho = |x: i64| (-((x * x) as f64) / (2.0 * sigma * sigma)).exp();
+        let total: f64 = (-bound..=bound).map(rho).sum();
+
+        let mean = samples.iter().sum::<i64>() as f64 / size as f64;
+        let variance = samples.iter().map(|&c| (c * c) as f64).sum::<f64>() / size as f64;
+        let expected_variance: f64 = (-bound..=bound).map(|x| (x * x) as f64 * rho(x)).sum::<f64>() / total;
+        assert!(mean.abs() < 0.05, "mean {} too far from 0", mean);
+        assert!((variance - expected_variance).abs() < 0.1, "variance {} vs {}", variance, expected_variance);
+
+        // chi-square over the 19 bins |x| <= 9 plus one bin for both tails
+        let mut chi2 = 0.0;
+        let mut tail_observed = 0.0;
+        let mut tail_expected = 0.0;
+        for x in -bound..=bound {
+            let observed = samples.iter().filter(|&&c| c == x).count() as f64;
+            let expected = size as f64 * rho(x) / total;
+            if x.abs() <= 9 {
+                chi2 += (observed - expected).powi(2) / expected;
+            } else {
+                tail_observed += observed;
+                tail_expected += expected;
+            }
+        }
+        chi2 += (tail_observed - tail_expected).powi(2) / tail_expected;
+        // 99.9% quantile of chi-square with 19 degrees of freedom is 43.8
+        assert!(chi2 < 43.8, "chi-square statistic {} too large", chi2);
+    }
+
+    // Test that keygen and encrypt accept an injected RNG and are deterministic for a deterministic RNG
+    #[test]
+    pub fn test_with_rng() {
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        let params = Parameters::default();
+        let m = Polynomial::new(vec![1, 0, 0, 1, 1]);
+
+        let (pk0, sk0) = keygen_with_rng(&params, &mut ChaCha20Rng::seed_from_u64(1));
+        let (pk1, sk1) = keygen_with_rng(&params, &mut ChaCha20Rng::seed_from_u64(1));
+        assert_eq!((&pk0, &sk0), (&pk1, &sk1));
+
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let ct0 = encrypt_with_rng(&pk0, &m, &params, &mut rng);
+        let ct1 = encrypt_with_rng(&pk0, &m, &params, &mut rng);
+        assert_ne!(ct0, ct1, "consecutive encryptions must use fresh randomness");
+        assert_eq!(decrypt(&sk0, &ct0, &params), m);
+        assert_eq!(decrypt(&sk0, &ct1, &params), m);
+
+        let (pk, sk) = SecretKey::generate_with_rng(&params, &mut rand::rngs::OsRng);
+        assert_eq!(sk.decrypt(&pk.encrypt_with_rng(&m, &mut rand::rngs::OsRng)).unwrap(), m);
+    }
+
+    // Test the negacyclic NTT multiplication against schoolbook multiplication mod x^n+1
+    #[test]
+    pub fn test_polymul_negacyclic() {
+        // default parameters and the squared modulus used for homomorphic multiplication
+        let params = Parameters::default();
+        let q2 = params.q * params.q;
+        for (q, psi) in [(params.q, params.omega), (q2, omega(q2, 2*params.n))] {
+            let a = gen_uniform_poly(params.n, q, None);
+            let b = gen_uniform_poly(params.n, q, None);
+            assert_eq!(polymul(&a, &b, q, &params.f), polymul_negacyclic(&a, &b, q, &params.f, psi));
+        }
+
+        // small ring Z_17[x]/(x^8+1), where x^7 * x = -1
+        let f = Polynomial::new(vec![1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        let psi = omega(17, 16);
+        let x7 = Polynomial::new(vec![0, 0, 0, 0, 0, 0, 0, 1]);
+        let x1 = Polynomial::new(vec![0, 1]);
+        assert_eq!(polymul_negacyclic(&x7, &x1, 17, &f, psi), Polynomial::new(vec![-1]));
+
+        // forward and inverse transforms are mutually inverse
+        let a = vec![3, 1, 4, 1, 5, 9, 2, 6];
+        assert_eq!(intt_negacyclic(&ntt_negacyclic(&a, psi, 17), psi, 17), a);
+    }
+
+    // Test the cached NTT tables against freshly computed ones
+    #[test]
+    pub fn test_ntt_context() {
+        let params = Parameters::default();
+        let ntt = params.ntt();
+        assert!(matches!(ntt, std::borrow::Cow::Borrowed(_)));
+        assert_eq!(*ntt, NttContext::from_params(&params));
+
+        // in-place transforms agree with the free functions and invert each other
+        let a = mod_coeffs(gen_uniform_poly(params.n, params.q, None), params.q);
+        let mut a_ntt = a.coeffs().to_vec();
+        ntt.forward(&mut a_ntt);
+        assert_eq!(a_ntt, ntt_negacyclic(a.coeffs(), params.omega, params.q));
+        ntt.inverse(&mut a_ntt);
+        assert_eq!(mod_coeffs(Polynomial::new(a_ntt), params.q), a);
+
+        // changing the modulus by hand bypasses the stale cache
+        let mut params_q2 = params.clone();
+        params_q2.q = params.q * params.q;
+        params_q2.omega = omega(params_q2.q, 2*params.n);
+        let ntt_q2 = params_q2.ntt();
+        assert!(matches!(ntt_q2, std::borrow::Cow::Owned(_)));
+        let b = gen_uniform_poly(params.n, params_q2.q, None);
+        assert_eq!(ntt_q2.polymul(&b, &b), polymul(&b, &b, params_q2.q, &params.f));
+    }
+
+    // Test NTT-domain arithmetic and the pre-transformed keys
+    #[test]
+    pub fn test_ntt_poly() {
+        let params = Parameters::default();
+        let (q, f) = (params.q, &params.f);
+        let ntt = params.ntt();
+        let a = gen_uniform_poly(params.n, q, None);
+        let b = gen_uniform_poly(params.n, q, Some(1));
+        let (a_ntt, b_ntt): (NttPoly, NttPoly) = (ntt.to_ntt(&a), ntt.to_ntt(&b));
+        assert_eq!(ntt.from_ntt(&(&a_ntt * &b_ntt)), polymul(&a, &b, q, f));
+        assert_eq!(ntt.from_ntt(&(&a_ntt + &b_ntt)), polyadd(&a, &b, q, f));
+        assert_eq!(ntt.from_ntt(&a_ntt), mod_coeffs(a, q));
+
+        // keys carry the transforms of their polynomials
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        assert_eq!(pk.b_ntt(), &pk.b().to_ntt(&ntt));
+        assert_eq!(pk.a_ntt(), &pk.a().to_ntt(&ntt));
+        assert_eq!(sk.s_ntt(), &sk.s().to_ntt(&ntt));
+
+        // many blocks under one loaded key still round-trip
+        let message = "x".repeat(3 * params.n / 8 + 5);
+        let keys = keygen_string(&params, Some(2));
+        let ciphertext = encrypt_string(&keys["public"], &message, &params, Some(3)).unwrap();
+        assert_eq!(decrypt_string(&keys["secret"], &ciphertext, &params).unwrap(), message);
+    }
+
+    // Test Montgomery and Barrett reduction against the remainder operator
+    #[test]
+    pub fn test_reduction() {
+        let values = gen_uniform_poly(1000, 1 << 30, Some(1));
+        for q in [3329u16, 12289] {
+            let (mont, barrett) = (Montgomery16::new(q), Barrett16::new(q));
+            for &v in values.coeffs() {
+                let (a, b) = ((v.unsigned_abs() % q as u64) as u16, (v.unsigned_abs() >> 16) as u16 % q);
+                let prod = mont.from_montgomery(mont.mul(mont.to_montgomery(a), mont.to_montgomery(b)));
+                assert_eq!(prod as u32, a as u32 * b as u32 % q as u32);
+                assert_eq!(barrett.reduce(v.unsigned_abs() as u32) as u64, v.unsigned_abs() as u32 as u64 % q as u64);
+            }
+            assert_eq!(barrett.reduce(u32::MAX) as u32, u32::MAX % q as u32);
+            for v in [i32::MIN, -(q as i32) / 2, -1, 0, q as i32 / 2 + 1, i32::MAX] {
+                assert_eq!(barrett.reduce_centered(v) as i64, mod_coeffs(Polynomial::new(vec![v as i64]), q as i64).coeffs().first().copied().unwrap_or(0));
+            }
+        }
+        for q in [3329u32, 12289, 12289 * 12289, (1 << 31) - 1] {
+            let (mont, barrett) = (Montgomery32::new(q), Barrett32::new(q));
+            for &v in values.coeffs() {
+                let (a, b) = (v.unsigned_abs() as u32 % q, (v.unsigned_abs() as u32).rotate_left(7) % q);
+                let prod = mont.from_montgomery(mont.mul(mont.to_montgomery(a), mont.to_montgomery(b)));
+                assert_eq!(prod as u64, a as u64 * b as u64 % q as u64);
+                let wide = v.unsigned_abs() * v.unsigned_abs();
+                assert_eq!(barrett.reduce(wide) as u64, wide % q as u64);
+                assert_eq!(barrett.reduce_centered(-v), mod_coeffs(Polynomial::new(vec![-v]), q as i64).coeffs().first().copied().unwrap_or(0));
+            }
+            assert_eq!(barrett.reduce(u64::MAX) as u64, u64::MAX % q as u64);
+        }
+
+        // polyadd and polyinv reduce through Barrett and agree with mod_coeffs
+        let params = Parameters::default();
+        let (q, f) = (params.q, &params.f);
+        let x = gen_uniform_poly(params.n, 1 << 40, None);
+        let y = gen_uniform_poly(params.n, 1 << 40, Some(1));
+        assert_eq!(polyadd(&x, &y, q, f), mod_coeffs(&x + &y, q));
+        assert_eq!(polyinv(&x, q), mod_coeffs(-&x, q));
+        let small = gen_uniform_poly(params.n, q, Some(2));
+        assert_eq!(polyadd(&small, &y, q, f), mod_coeffs(&small + &y, q));
+
+        // the NTT multiplies with the 16-bit reducers below 2^15 and the 32-bit ones above
+        for q in [3329, 12289, 1073479681] {
+            let params = Parameters::builder().n(128).q(q).build().unwrap();
+            let a = gen_uniform_poly(params.n, q, Some(3));
+            let b = gen_uniform_poly(params.n, q, Some(4));
+            assert_eq!(params.ntt().polymul(&a, &b), polymul(&a, &b, q, &params.f));
+        }
+    }
+
+    // Test the compact polynomial type against Polynomial<i64> arithmetic for each word size
+    #[test]
+    pub fn test_ring_poly() {
+        let params = Parameters::default();
+        let (n, q, f) = (params.n, params.q, &params.f);
+        let ntt = params.ntt();
+        let a = gen_uniform_poly(n, q, None);
+        let b = gen_uniform_poly(n, q, Some(1));
+
+        let (a16, b16) = (RingPoly::<u16>::from_poly(&a, n, q), RingPoly::<u16>::from_poly(&b, n, q));
+        assert_eq!(a16.to_poly(), mod_coeffs(a.clone(), q));
+        assert_eq!((&a16 + &b16).to_poly(), polyadd(&a, &b, q, f));
+        assert_eq!((&a16 - &b16).to_poly(), polysub(&a, &b, q, f));
+        assert_eq!((-&a16).to_poly(), polyinv(&a, q));
+        assert_eq!(a16.mul(&b16, &ntt).to_poly(), polymul(&a, &b, q, f));
+
+        let (a32, b32) = (RingPoly::<u32>::from_poly(&a, n, q), RingPoly::<u32>::from_poly(&b, n, q));
+        let (a64, b64) = (RingPoly::<u64>::from_poly(&a, n, q), RingPoly::<u64>::from_poly(&b, n, q));
+        assert_eq!(a32.mul(&b32, &ntt).to_poly(), a64.mul(&b64, &ntt).to_poly());
+        assert_eq!(std::mem::size_of_val(a16.coeffs()), 2 * n);
+
+        // polynomials longer than n are folded modulo x^n+1
+        assert_eq!(RingPoly::<u16>::from_poly(&(&a * &b), n, q).to_poly(), polymul(&a, &b, q, f));
+
+        // keys and ciphertexts hold n centered coefficients per component
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let ct = pk.encrypt(&Polynomial::new(vec![1, 1]), Some(2));
+        assert_eq!((pk.a().len(), sk.s().len(), ct.c0().len()), (n, n, n));
+        assert_eq!(sk.decrypt(&ct).unwrap(), Polynomial::new(vec![1, 1]));
+    }
+
+    // Test the bit-packed wire format for keys and ciphertexts
+    #[test]
+    pub fn test_packed_format() {
+        let params = Parameters::default();
+        let (n, q) = (params.n, params.q);
+        assert_eq!(coeff_bits(q), 14);
+        assert_eq!(coeff_bits(3329), 12);
+
+        // odd widths round-trip across byte boundaries, and out-of-range values are rejected
+        let coeffs = vec![0, 1, -1, 6144, -6144, 17, -3000];
+        assert_eq!(unpack_coeffs(&pack_coeffs(&coeffs, q, 14), 7, q, 14).unwrap(), coeffs);
+        assert!(matches!(unpack_coeffs(&[0xff, 0xff], 1, q, 14), Err(RingLweError::InvalidCoefficient { .. })));
+        assert!(matches!(unpack_coeffs(&[0; 3], 1, q, 14), Err(RingLweError::LengthMismatch { expected: 2, found: 3 })));
+
+        // 14 bits per public key coefficient and 2 bits per ternary secret coefficient
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        assert_eq!(pk.to_packed().len(), 2 * n * 14 / 8);
+        assert_eq!(sk.to_packed().len(), n / 4);
+        assert_eq!(PublicKey::from_packed(&pk.to_packed(), &params).unwrap(), pk);
+        assert_eq!(SecretKey::from_packed(&sk.to_packed(), &params).unwrap(), sk);
+        let ct = pk.encrypt(&Polynomial::new(vec![1, 0, 1]), Some(2));
+        assert_eq!(Ciphertext::from_packed(&ct.to_packed(), &params).unwrap(), ct);
+
+        // string round trip, with the packed strings several times smaller
+        let message = "packed wire format";
+        let keys = keygen_string_with_format(&params, Some(3), WireFormat::Packed);
+        let ct_packed = encrypt_string_with_format(&keys["public"], message, &params, Some(4), WireFormat::Packed).unwrap();
+        assert_eq!(decrypt_string_with_format(&keys["secret"], &ct_packed, &params, WireFormat::Packed).unwrap(), message);
+        let keys_bincode = keygen_string(&params, Some(3));
+        assert!(4 * keys["public"].len() < keys_bincode["public"].len());
+        assert!(matches!(
+            decrypt_string_with_format(&keys["secret"], &ct_packed[..ct_packed.len() - 8], &params, WireFormat::Packed),
+            Err(RingLweError::Decode(_))
+        ));
+    }
+
+    // Test Kyber-style ciphertext compression and its failure estimate
+    #[test]
+    pub fn test_compress_ciphertext() {
+        let params = Parameters::default();
+        let q = params.q;
+
+        // Decompress_d(Compress_d(x)) stays within q / 2^(d+1) of x
+        for d in [1, 4, 10, 14] {
+            for x in [-6144, -3000, -1, 0, 1, 3072, 6144] {
+                let err = (decompress_d(compress_d(x, q, d), q, d) - x).rem_euclid(q);
+                assert!(err.min(q - err) <= q / (1 << (d + 1)) + 1);
+            }
+        }
+
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let m = Polynomial::new(vec![1, 0, 1, 1, 0, 0, 1]);
+        for i in 0..8 {
+            let compressed = compress_ciphertext(&pk.encrypt(&m, Some(i)), 10, 4).unwrap();
+            assert_eq!(sk.decrypt(&decompress_ciphertext(&compressed)).unwrap(), m);
+
+            // 14 bits per coefficient become 10 + 4
+            let packed = compressed.to_packed();
+            assert_eq!(packed.len(), params.n * 14 / 8);
+            assert_eq!(CompressedCiphertext::from_packed(&packed, 10, 4, &params).unwrap(), compressed);
+        }
+
+        // lossless at full width, and out-of-range widths are rejected
+        let ct = pk.encrypt(&m, Some(9));
+        assert_eq!(decompress_ciphertext(&compress_ciphertext(&ct, 14, 14).unwrap()), ct);
+        assert!(matches!(compress_ciphertext(&ct, 0, 4), Err(RingLweError::InvalidParameters(_))));
+        assert!(matches!(compress_ciphertext(&ct, 10, 15), Err(RingLweError::InvalidParameters(_))));
+
+        // the estimate grows as fewer bits are kept
+        assert_eq!(compression_failure_probability(&params, 14, 14), 0.0);
+        let probs: Vec<f64> = [10, 9, 8, 7].iter().map(|&du| compression_failure_probability(&params, du, 4)).collect();
+        assert!(probs.windows(2).all(|w| w[0] <= w[1]));
+        assert!(probs[0] < 1e-30 && probs[3] > 1e-3);
+    }
+
+    // Test the versioned container around keys and ciphertexts
+    #[test]
+    pub fn test_container_format() {
+        let params = Parameters::default();
+        let keys = keygen_string(&params, Some(1));
+        let ct_string = encrypt_string(&keys["public"], "container", &params, Some(2)).unwrap();
+
+        let ct_bytes = decode_bytes(&ct_string).unwrap();
+        let (header, payload) = decode_container(&ct_bytes).unwrap();
+        assert_eq!(&ct_bytes[..4], &MAGIC);
+        assert_eq!(header, Header::new(ObjectType::Ciphertext, &params, WireFormat::Bincode));
+        assert_eq!(encode_container(&header, payload), ct_bytes);
+
+        // material written under other parameters is refused instead of decrypting to garbage
+        let other = Parameters::builder().t(4).build().unwrap();
+        assert!(matches!(decrypt_string(&keys["secret"], &ct_string, &other), Err(RingLweError::ParameterMismatch(_))));
+        assert!(matches!(encrypt_string(&keys["public"], "x", &other, None), Err(RingLweError::ParameterMismatch(_))));
+        let cbd = Parameters::builder().error_distribution(ErrorDistribution::CenteredBinomial { eta: 2 }).build().unwrap();
+        assert!(matches!(encrypt_string(&keys["public"], "x", &cbd, None), Err(RingLweError::ParameterMismatch(_))));
+        let other_secret = encode_bytes(&encode_container(&Header { secret_dist: 1, ..header.clone() }, payload));
+        assert!(matches!(decrypt_string(&keys["secret"], &other_secret, &params), Err(RingLweError::ParameterMismatch(_))));
+
+        // corrupted payloads fail the checksum, newer versions are refused
+        let mut corrupted = ct_bytes.clone();
+        corrupted[40] ^= 1;
+        assert!(matches!(decode_container(&corrupted), Err(RingLweError::Decode(_))));
+        let mut newer = ct_bytes.clone();
+        newer[4] = FORMAT_VERSION + 1;
+        assert!(matches!(decode_container(&newer), Err(RingLweError::Decode(_))));
+
+        // the checksum is optional, and bare payloads from before containers still load
+        let unchecked = Header { checksum: false, ..header };
+        let unchecked_string = encode_bytes(&encode_container(&unchecked, payload));
+        assert_eq!(decrypt_string(&keys["secret"], &unchecked_string, &params).unwrap(), "container");
+        let sk = SecretKey::decode(&keys["secret"], &params, WireFormat::Bincode).unwrap();
+        assert_eq!(decrypt_string(&sk.to_base64(), &compress(&deserialize_coeffs(payload).unwrap()), &params).unwrap(), "container");
+
+        // version 1 containers hold packed ciphertext blocks without the noise estimate
+        let packed_bytes = decode_bytes(&encrypt_bytes(&keys["public"], b"legacy", &params, Some(3), WireFormat::Packed).unwrap()).unwrap();
+        let (header, payload) = decode_container(&packed_bytes).unwrap();
+        let legacy: Vec<u8> = payload
+            .chunks(Ciphertext::packed_len(&params))
+            .flat_map(|block| block[..Ciphertext::packed_coeffs_len(&params)].to_vec())
+            .collect();
+        let legacy_string = encode_bytes(&encode_container(&Header { version: 1, ..header }, &legacy));
+        assert_eq!(decrypt_bytes(&keys["secret"], &legacy_string, &params, WireFormat::Packed).unwrap(), b"legacy");
+    }
+
+    // Test PEM-style armor around keys and ciphertexts
+    #[test]
+    pub fn test_armor() {
+        let params = Parameters::default();
+        let keys = keygen_string(&params, Some(1));
+        let pk_armored = armor(ObjectType::PublicKey, &keys["public"]);
+        let sk_armored = armor(ObjectType::SecretKey, &keys["secret"]);
+
+        let lines: Vec<&str> = pk_armored.lines().collect();
+        assert_eq!(lines[0], "-----BEGIN RING-LWE PUBLIC KEY-----");
+        assert_eq!(*lines.last().unwrap(), "-----END RING-LWE PUBLIC KEY-----");
+        assert!(lines[1..lines.len() - 1].iter().all(|line| line.len() <= 64));
+        assert_eq!(dearmor(&pk_armored).unwrap(), (Some(ObjectType::PublicKey), keys["public"].clone()));
+        assert_eq!(dearmor(&keys["public"]).unwrap(), (None, keys["public"].clone()));
+
+        // armored and bare input are accepted alike, CRLF line endings included
+        let ct_string = encrypt_string(&pk_armored.replace('\n', "\r\n"), "armor", &params, Some(2)).unwrap();
+        let ct_armored = armor(ObjectType::Ciphertext, &ct_string);
+        assert_eq!(decrypt_string(&sk_armored, &ct_armored, &params).unwrap(), "armor");
+        assert_eq!(decrypt_string(&keys["secret"], &ct_armored, &params).unwrap(), "armor");
+
+        // the wrong kind of object, mismatched or missing END lines are refused
+        assert!(matches!(encrypt_string(&sk_armored, "x", &params, None), Err(RingLweError::ParameterMismatch(_))));
+        let mismatched = pk_armored.replace("END RING-LWE PUBLIC KEY", "END RING-LWE SECRET KEY");
+        assert!(matches!(dearmor(&mismatched), Err(RingLweError::Decode(_))));
+        let truncated: String = pk_armored.lines().take(3).collect::<Vec<_>>().join("\n");
+        assert!(matches!(dearmor(&truncated), Err(RingLweError::Decode(_))));
+        assert!(matches!(dearmor("-----BEGIN RING-LWE PRIVATE KEY-----\n-----END RING-LWE PRIVATE KEY-----"), Err(RingLweError::Decode(_))));
+    }
+
+    // Test serde round trips through a human-readable and a binary format
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn test_serde() {
+        let params = Parameters::builder().error_distribution(ErrorDistribution::CenteredBinomial { eta: 2 }).build().unwrap();
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let ct = pk.encrypt(&Polynomial::new(vec![1, 0, 1]), Some(2));
+
+        // JSON keeps the packed coefficients as base64
+        let json = serde_json::to_value(&pk).unwrap();
+        assert_eq!(json["params"]["q"], 12289);
+        assert_eq!(json["packed"].as_str().unwrap(), encode_bytes(&pk.to_packed()));
+        assert_eq!(serde_json::from_value::<PublicKey>(json).unwrap(), pk);
+        let sk_json = serde_json::to_string(&sk).unwrap();
+        assert_eq!(serde_json::from_str::<SecretKey>(&sk_json).unwrap(), sk);
+        assert_eq!(serde_json::from_str::<Parameters>(&serde_json::to_string(&params).unwrap()).unwrap(), params);
+        let chained = Parameters::builder().q(1073479681).modulus_chain(&[786433, 12289]).build().unwrap();
+        assert_eq!(serde_json::from_str::<Parameters>(&serde_json::to_string(&chained).unwrap()).unwrap(), chained);
+
+        // binary formats carry the packed bytes directly
+        let ct_bytes = bincode::serialize(&ct).unwrap();
+        assert!(ct_bytes.len() < ct.to_packed().len() + 100);
+        assert_eq!(bincode::deserialize::<Ciphertext>(&ct_bytes).unwrap(), ct);
+        assert_eq!(sk.decrypt(&bincode::deserialize::<Ciphertext>(&ct_bytes).unwrap()).unwrap(), Polynomial::new(vec![1, 0, 1]));
+        let sum = add(&ct, &ct).unwrap();
+        let sum_json = serde_json::to_string(&sum).unwrap();
+        assert_eq!(serde_json::from_str::<Ciphertext>(&sum_json).unwrap().noise_estimate(), sum.noise_estimate());
+
+        // invalid parameters and truncated coefficients are rejected
+        let bad_params = r#"{"n":1000,"q":12289,"t":2,"sigma":8.0,"error_dist":"Ternary"}"#;
+        assert!(serde_json::from_str::<Parameters>(bad_params).is_err());
+        let mut truncated = serde_json::to_value(&pk).unwrap();
+        truncated["packed"] = serde_json::Value::from(encode_bytes(&pk.to_packed()[..100]));
+        assert!(serde_json::from_value::<PublicKey>(truncated).is_err());
+    }
+
+    // Test the length-prefixed plaintext codec and byte encryption
+    #[test]
+    pub fn test_message_codec() {
+        // binary data with zero bytes and non-ASCII UTF-8 round-trip unchanged
+        let data: Vec<u8> = (0..=255u8).chain([0, 0]).collect();
+        for t in [2, 3, 16, 17, 257] {
+            let params = Parameters::builder().n(16).q(12289).t(t).build().unwrap();
+            let blocks = encode_message(&data, &params);
+            let bits = bits_per_coeff(t) as usize;
+            assert_eq!(blocks.len(), ((LENGTH_HEADER_BYTES + data.len()) * 8).div_ceil(bits).div_ceil(16));
+            assert!(blocks.iter().all(|block| block.coeffs().iter().all(|&c| (0..1 << bits).contains(&c))));
+            assert_eq!(decode_message(&blocks, &params).unwrap(), data);
+        }
+
+        let params = Parameters::default();
+        let keys = keygen_string(&params, Some(1));
+        let ct_string = encrypt_bytes(&keys["public"], &data, &params, Some(2), WireFormat::Packed).unwrap();
+        assert_eq!(decrypt_bytes(&keys["secret"], &ct_string, &params, WireFormat::Packed).unwrap(), data);
+        let message = "grüße, 世界\0";
+        let ct_string = encrypt_string(&keys["public"], message, &params, Some(3)).unwrap();
+        assert_eq!(decrypt_string(&keys["secret"], &ct_string, &params).unwrap(), message);
+        let ct_string = encrypt_bytes(&keys["public"], &[0xff, 0xfe], &params, Some(4), WireFormat::Bincode).unwrap();
+        assert!(matches!(decrypt_string(&keys["secret"], &ct_string, &params), Err(RingLweError::Decode(_))));
+
+        // a truncated message or an oversized coefficient is an error, not garbage
+        let small = Parameters::builder().n(16).build().unwrap();
+        let blocks = encode_message(&[1, 2, 3], &small);
+        assert!(matches!(decode_message(&blocks[..blocks.len() - 1], &small), Err(RingLweError::LengthMismatch { expected: 11, found: 10 })));
+        assert!(matches!(decode_message(&[Polynomial::new(vec![0, 2])], &params), Err(RingLweError::InvalidCoefficient { index: 1, .. })));
+    }
+
+    // Test the integer, coefficient-vector and signed plaintext encoders
+    #[test]
+    pub fn test_encoders() {
+        let params = Parameters::builder().n(16).t(17).build().unwrap();
+        let integer = IntegerEncoder::new(3, &params).unwrap();
+        for value in [0, 1, -1, 42, -12345, 43046720] {
+            assert_eq!(integer.decode(&integer.encode(value).unwrap()).unwrap(), value);
+        }
+        // decoding evaluates at the base, so unnormalized digits carry over
+        assert_eq!(integer.decode(&Polynomial::new(vec![5, 4])).unwrap(), 17);
+        assert!(matches!(integer.encode(i64::MAX), Err(RingLweError::LengthMismatch { expected: 16, found: 40 })));
+        assert!(IntegerEncoder::new(18, &params).is_err());
+        // the largest base whose digits of either sign still decode centered mod t
+        let large = IntegerEncoder::new(9, &params).unwrap();
+        for value in [8, 9, -8, 100, -100, 16, 123456789] {
+            assert_eq!(large.decode(&large.encode(value).unwrap()).unwrap(), value);
+        }
+        assert!(IntegerEncoder::new(10, &params).is_err());
+        assert!(IntegerEncoder::new(17, &params).is_err());
+
+        let coeffs = CoeffEncoder::new(&params);
+        let signed = SignedEncoder::new(&params);
+        assert_eq!(coeffs.decode(&coeffs.encode(&[16, 0, 5]).unwrap())[..4], [16, 0, 5, 0]);
+        assert_eq!(signed.decode(&signed.encode(&[-8, 8, 0, -1]).unwrap())[..5], [-8, 8, 0, -1, 0]);
+        assert!(matches!(coeffs.encode(&[1, 17]), Err(RingLweError::InvalidCoefficient { index: 1, .. })));
+        assert!(matches!(signed.encode(&[9]), Err(RingLweError::InvalidCoefficient { index: 0, .. })));
+        assert!(matches!(signed.encode(&[0; 17]), Err(RingLweError::LengthMismatch { .. })));
+
+        // encodings survive encryption and homomorphic addition
+        let (pk, sk) = keygen(&params, Some(1));
+        let a = encrypt(&pk, &signed.encode(&[-3, 7]).unwrap(), &params, Some(2));
+        let b = encrypt(&pk, &signed.encode(&[5, -7]).unwrap(), &params, Some(3));
+        let sum = decrypt(&sk, &[&a[0] + &b[0], &a[1] + &b[1]], &params);
+        assert_eq!(signed.decode(&sum)[..3], [2, 0, 0]);
+    }
+
+    // Test BFV evaluation with relinearization across repeated multiplications
+    #[test]
+    pub fn test_evaluate() {
+        let params = Parameters::builder().n(256).q(1073479681).build().unwrap();
+        let (t, f) = (params.t, &params.f);
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let rlk = sk.relin_key(1 << 8, Some(2)).unwrap();
+        assert_eq!(rlk.b_ntt().len(), RelinKey::digits(1 << 8, params.q));
+        assert_eq!(RelinKey::digits(1 << 8, params.q), 4);
+
+        let m: Vec<Polynomial<i64>> = (0..3).map(|i| gen_uniform_poly(params.n, t, Some(10 + i))).collect();
+        let ct: Vec<Ciphertext> = m.iter().enumerate().map(|(i, m)| pk.encrypt(m, Some(20 + i as u64))).collect();
+
+        assert_eq!(sk.decrypt(&add(&ct[0], &ct[1]).unwrap()).unwrap(), polyadd(&m[0], &m[1], t, f));
+        assert_eq!(sk.decrypt(&sub(&ct[0], &ct[1]).unwrap()).unwrap(), polysub(&m[0], &m[1], t, f));
+        assert_eq!(sk.decrypt(&negate(&ct[0])).unwrap(), mod_coeffs(-&m[0], t));
+
+        // the unrelinearized product decrypts with sk and sk^2
+        let product = multiply(&ct[0], &ct[1]).unwrap();
+        let ntt = params.ntt();
+        let s_squared = sk.s().mul(sk.s(), &ntt);
+        let scaled = &(product.c0() + &product.c1().mul(sk.s(), &ntt)) + &product.c2().mul(&s_squared, &ntt);
+        let decoded = Polynomial::new(scaled.to_centered().iter().map(|&c| nearest_int(c * t, params.q).rem_euclid(t)).collect());
+        let expected = polymul(&m[0], &m[1], t, f);
+        assert_eq!(decoded, expected);
+
+        // the tensor product is exact modulo cached auxiliary primes that are 1 mod 2n
+        assert!(matches!(params.tensor_ntt(), Cow::Borrowed(_)));
+        let primes: Vec<i64> = params.tensor_ntt().iter().map(|ntt| ntt.q()).collect();
+        assert!(primes.iter().all(|&p| is_prime(p) && p < 1 << 31 && p % (2 * params.n as i64) == 1));
+        assert!(primes.iter().map(|&p| p as f64).product::<f64>() > params.n as f64 * (params.q as f64).powi(2));
+
+        // relinearized products stay two components and multiply again
+        let product = relinearize(&product, &rlk).unwrap();
+        assert_eq!(sk.decrypt(&product).unwrap(), expected);
+        let product = relinearize(&multiply(&product, &ct[2]).unwrap(), &rlk).unwrap();
+        assert_eq!(sk.decrypt(&product).unwrap(), polymul(&expected, &m[2], t, f));
+
+        let other = Parameters::builder().n(256).q(1073479681).t(4).build().unwrap();
+        let (other_pk, _) = SecretKey::generate(&other, Some(3));
+        assert!(matches!(multiply(&ct[0], &other_pk.encrypt(&m[0], None)), Err(RingLweError::ParameterMismatch(_))));
+    }
+
+    // Test ciphertext-plaintext addition, subtraction and multiplication
+    #[test]
+    pub fn test_plain_ops() {
+        let params = Parameters::builder().t(17).build().unwrap();
+        let (t, f) = (params.t, &params.f);
+        let reduce = |x: Polynomial<i64>| Polynomial::new(x.coeffs().iter().map(|c| c.rem_euclid(t)).collect());
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let m = gen_uniform_poly(params.n, t, Some(2));
+        let plain = gen_uniform_poly(params.n, t, Some(3));
+        let ct = pk.encrypt(&m, Some(4));
+
+        assert_eq!(sk.decrypt(&add_plain(&ct, &plain)).unwrap(), reduce(polyadd(&m, &plain, t, f)));
+        assert_eq!(sk.decrypt(&sub_plain(&ct, &plain)).unwrap(), reduce(polysub(&m, &plain, t, f)));
+        // negative and unreduced plaintext coefficients are taken mod t
+        assert_eq!(sk.decrypt(&add_plain(&ct, &Polynomial::new(vec![-1, 18]))).unwrap(), reduce(polyadd(&m, &Polynomial::new(vec![16, 1]), t, f)));
+
+        // a sparse plaintext keeps the noise growth small enough for the default modulus
+        let monomial = Polynomial::new(vec![0, 0, 0, 16]);
+        assert_eq!(sk.decrypt(&multiply_plain(&ct, &monomial)).unwrap(), reduce(polymul(&m, &monomial, t, f)));
+        let sum = add(&multiply_plain(&ct, &Polynomial::new(vec![2])), &ct).unwrap();
+        assert_eq!(sk.decrypt(&sum).unwrap(), reduce(&m * 3));
+    }
+
+    // Test the measured noise budget against the heuristic estimate carried by ciphertexts
+    #[test]
+    pub fn test_noise_budget() {
+        let params = Parameters::default();
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let m = gen_uniform_poly(params.n, params.t, Some(2));
+        let ct = pk.encrypt(&m, Some(3));
+        let budget = sk.noise_budget(&ct).unwrap();
+        assert_eq!(budget, noise_budget(&sk.clone().into_poly(), &ct.clone().into_array(), &params));
+        assert!(ct.estimated_noise_budget() > 3.0 && ct.estimated_noise_budget() <= budget && budget < ct.estimated_noise_budget() + 3.0);
+
+        // multiplying by 1 + x + x^2 uses up budget, and the estimate stays below the measurement
+        let (t, f) = (params.t, &params.f);
+        let plain = Polynomial::new(vec![1, 1, 1]);
+        let (mut scaled, mut expected) = (ct.clone(), m.clone());
+        let mut budgets = vec![];
+        while scaled.estimated_noise_budget() > 0.0 {
+            budgets.push(sk.noise_budget(&scaled).unwrap());
+            assert!(scaled.estimated_noise_budget() <= budgets[budgets.len() - 1]);
+            assert_eq!(sk.decrypt(&scaled).unwrap(), expected);
+            scaled = multiply_plain(&scaled, &plain);
+            expected = mod_coeffs(polymul(&expected, &plain, t, f), t);
+        }
+        assert!(budgets.len() >= 3 && budgets.windows(2).all(|w| w[1] < w[0]));
+        let compressed = decompress_ciphertext(&compress_ciphertext(&ct, 8, 3).unwrap());
+        assert!(compressed.noise_estimate() > ct.noise_estimate());
+        assert!(compressed.estimated_noise_budget() <= sk.noise_budget(&compressed).unwrap());
+
+        // packing keeps the estimate; ciphertexts of unknown history get an estimated budget of 0
+        let evaluated = multiply_plain(&ct, &plain);
+        assert!(evaluated.noise_estimate() > ct.noise_estimate());
+        let loaded = Ciphertext::from_packed(&evaluated.to_packed(), &params).unwrap();
+        assert_eq!(loaded.noise_estimate(), evaluated.noise_estimate());
+        let legacy = &evaluated.to_packed()[..Ciphertext::packed_coeffs_len(&params)];
+        for reloaded in [Ciphertext::from_packed(legacy, &params).unwrap(), Ciphertext::from_coeffs(&evaluated.to_coeffs(), &params).unwrap()] {
+            assert_eq!(reloaded, evaluated);
+            assert_eq!(reloaded.estimated_noise_budget(), 0.0);
+        }
+        let mut corrupted = evaluated.to_packed();
+        let noise_at = corrupted.len() - 8;
+        corrupted[noise_at..].copy_from_slice(&f64::NAN.to_le_bytes());
+        assert!(matches!(Ciphertext::from_packed(&corrupted, &params), Err(RingLweError::Decode(_))));
+
+        // a multiplication costs far more than an addition
+        let params = Parameters::builder().n(256).q(1073479681).build().unwrap();
+        let (pk, sk) = SecretKey::generate(&params, Some(4));
+        let rlk = sk.relin_key(1 << 8, Some(5)).unwrap();
+        let m = gen_uniform_poly(params.n, params.t, Some(6));
+        let (a, b) = (pk.encrypt(&m, Some(7)), pk.encrypt(&m, Some(8)));
+        let product = relinearize(&multiply(&a, &b).unwrap(), &rlk).unwrap();
+        let fresh = sk.noise_budget(&a).unwrap();
+        assert!(fresh - sk.noise_budget(&add(&a, &b).unwrap()).unwrap() < 1.5);
+        // one multiplication scales the noise by about t*n, up to a small constant factor
+        let expected_loss = ((params.t * params.n as i64) as f64).log2();
+        assert!((fresh - sk.noise_budget(&product).unwrap() - expected_loss).abs() < 2.0);
+        assert!(product.estimated_noise_budget() <= sk.noise_budget(&product).unwrap());
+        assert!(product.estimated_noise_budget() > 0.0);
+    }
+
+    // Test Galois automorphisms and slot rotations through key switching
+    #[test]
+    pub fn test_galois() {
+        let params = Parameters::default();
+        let (n, t) = (params.n, params.t);
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let elements = [row_rotation_element(1, n), row_rotation_element(-1, n), column_rotation_element(n)];
+        assert_eq!(elements, [3, 683, 2047]);
+        assert_eq!(row_rotation_element(n as i64 / 2, n), 1);
+        let keys = gen_galois_keys(&sk, &elements, 4, Some(2)).unwrap();
+
+        // the ciphertext of m decrypts to m(x^k) after apply_galois
+        let m = gen_uniform_poly(n, t, Some(3));
+        let ct = pk.encrypt(&m, Some(4));
+        let galois = |k: usize| {
+            let image = RingPoly::<u32>::from_poly(&m, n, t).automorphism(k);
+            Polynomial::new(image.coeffs().iter().map(|&c| c as i64).collect())
+        };
+        for &k in &elements {
+            let image = apply_galois(&ct, k, &keys).unwrap();
+            assert_eq!(sk.decrypt(&image).unwrap(), galois(k));
+            assert!(image.estimated_noise_budget() > 0.0);
+            assert!(image.estimated_noise_budget() <= sk.noise_budget(&image).unwrap());
+        }
+
+        // rotations compose, and swapping the rows twice is the identity
+        let rotated = rotate_rows(&rotate_rows(&ct, 1, &keys).unwrap(), -1, &keys).unwrap();
+        assert_eq!(sk.decrypt(&rotated).unwrap(), m);
+        let swapped = rotate_columns(&rotate_columns(&ct, &keys).unwrap(), &keys).unwrap();
+        assert_eq!(sk.decrypt(&swapped).unwrap(), m);
+
+        // missing keys and even elements are rejected
+        assert!(matches!(rotate_rows(&ct, 2, &keys), Err(RingLweError::InvalidParameters(_))));
+        assert!(matches!(apply_galois(&ct, 4, &keys), Err(RingLweError::InvalidParameters(_))));
+        assert!(matches!(sk.galois_keys(&[3, 2048], 16, None), Err(RingLweError::InvalidParameters(_))));
+        assert_eq!(sk.galois_keys(&[5, 5], 16, Some(2)).unwrap().elements(), vec![5]);
+    }
+
+    // Test slot-wise batch encoding and its interaction with rotations
+    #[test]
+    pub fn test_batch_encoder() {
+        let params = Parameters::builder().n(16).q(1073479681).t(97).build().unwrap();
+        let (n, t) = (params.n, params.t as u64);
+        let encoder = BatchEncoder::new(&params).unwrap();
+        assert_eq!(encoder.slot_count(), n);
+
+        let a: Vec<u64> = (0..n as u64).map(|i| (7 * i + 3) % t).collect();
+        let b: Vec<u64> = (0..n as u64).map(|i| (i * i + 50) % t).collect();
+        let (pa, pb) = (encoder.encode(&a).unwrap(), encoder.encode(&b).unwrap());
+        assert_eq!(encoder.decode(&pa), a);
+        assert!(pa.coeffs().iter().all(|&c| (0..t as i64).contains(&c)));
+
+        // addition and multiplication act slot-wise
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let rlk = sk.relin_key(1 << 8, Some(2)).unwrap();
+        let (ca, cb) = (pk.encrypt(&pa, Some(3)), pk.encrypt(&pb, Some(4)));
+        let slotwise = |op: fn(u64, u64) -> u64| a.iter().zip(&b).map(|(&x, &y)| op(x, y) % t).collect::<Vec<u64>>();
+        assert_eq!(encoder.decode(&sk.decrypt(&add(&ca, &cb).unwrap()).unwrap()), slotwise(|x, y| x + y));
+        let product = relinearize(&multiply(&ca, &cb).unwrap(), &rlk).unwrap();
+        assert_eq!(encoder.decode(&sk.decrypt(&product).unwrap()), slotwise(|x, y| x * y));
+        assert_eq!(encoder.decode(&sk.decrypt(&multiply_plain(&ca, &pb)).unwrap()), slotwise(|x, y| x * y));
+
+        // rotate_rows rotates both rows left and rotate_columns swaps them
+        let elements = [row_rotation_element(1, n), row_rotation_element(-2, n), column_rotation_element(n)];
+        let keys = sk.galois_keys(&elements, 1 << 8, Some(5)).unwrap();
+        let (row0, row1) = a.split_at(n / 2);
+        let rotate = |steps: usize| [row0, row1].iter().flat_map(|row| row.iter().cycle().skip(steps).take(n / 2).copied()).collect::<Vec<u64>>();
+        assert_eq!(encoder.decode(&sk.decrypt(&rotate_rows(&ca, 1, &keys).unwrap()).unwrap()), rotate(1));
+        assert_eq!(encoder.decode(&sk.decrypt(&rotate_rows(&ca, -2, &keys).unwrap()).unwrap()), rotate(n / 2 - 2));
+        assert_eq!(encoder.decode(&sk.decrypt(&rotate_columns(&ca, &keys).unwrap()).unwrap()), [row1, row0].concat());
+
+        // batching needs t prime and 1 mod 2n
+        assert!(matches!(BatchEncoder::new(&Parameters::default()), Err(RingLweError::InvalidParameters(_))));
+        assert!(BatchEncoder::new(&Parameters::builder().n(16).q(1073479681).t(33).build().unwrap()).is_err());
+        assert!(matches!(encoder.encode(&[97]), Err(RingLweError::InvalidCoefficient { index: 0, value: 97, modulus: 97 })));
+        assert!(matches!(encoder.encode(&[0; 17]), Err(RingLweError::LengthMismatch { expected: 16, found: 17 })));
+    }
+
+    // Test modulus switching down the modulus chain
+    #[test]
+    pub fn test_mod_switch() {
+        let params = Parameters::builder().q(1073479681).modulus_chain(&[786433, 12289]).build().unwrap();
+        let (pk, sk) = SecretKey::generate(&params, Some(1));
+        let rlk = sk.relin_key(1 << 8, Some(2)).unwrap();
+        let m: Vec<Polynomial<i64>> = (0..2).map(|i| gen_uniform_poly(params.n, params.t, Some(3 + i))).collect();
+        let (a, b) = (pk.encrypt(&m[0], Some(5)), pk.encrypt(&m[1], Some(6)));
+        let sk_at = |q: i64| SecretKey::new(sk.s().to_poly(), &params.at_modulus(q).unwrap());
+
+        // a product computed at the large modulus still decrypts after switching all the way down
+        let product = relinearize(&multiply(&a, &b).unwrap(), &rlk).unwrap();
+        let expected = polymul(&m[0], &m[1], params.t, &params.f);
+        let small = mod_switch(&product, params.q, 12289).unwrap();
+        assert_eq!((small.params().q, small.params().modulus_chain.len()), (12289, 0));
+        assert_eq!(sk_at(12289).decrypt(&small).unwrap(), expected);
+        assert!(small.estimated_noise_budget() > 0.0);
+        assert!(small.estimated_noise_budget() <= sk_at(12289).noise_budget(&small).unwrap());
+        assert!(Ciphertext::packed_len(small.params()) < Ciphertext::packed_len(&params));
+
+        // switching one step at a time follows the chain
+        let middle = mod_switch_to_next(&a).unwrap();
+        assert_eq!(middle.params(), &params.at_modulus(786433).unwrap());
+        assert_eq!(sk_at(786433).decrypt(&middle).unwrap(), m[0]);
+        let bottom = mod_switch_to_next(&middle).unwrap();
+        assert_eq!(sk_at(12289).decrypt(&bottom).unwrap(), m[0]);
+        assert!(matches!(mod_switch_to_next(&bottom), Err(RingLweError::InvalidParameters(_))));
+
+        // the ciphertext must be under q_from and q_to must be on its chain
+        assert!(matches!(mod_switch(&middle, params.q, 12289), Err(RingLweError::ParameterMismatch(_))));
+        assert!(matches!(mod_switch(&a, params.q, 40961), Err(RingLweError::InvalidParameters(_))));
+        assert!(matches!(mod_switch(&middle, 786433, params.q), Err(RingLweError::InvalidParameters(_))));
+
+        // chain entries are decreasing primes above t that are 1 mod 2n
+        let chain = |chain: &[i64]| Parameters::builder().q(1073479681).t(17).modulus_chain(chain).build();
+        assert!(chain(&[12289, 786433]).is_err());
+        assert!(chain(&[786432]).is_err());
+        assert!(chain(&[786433, 7681]).is_err());
+        assert!(chain(&[12289, 17]).is_err());
+        assert!(chain(&[786433, 40961, 12289]).is_ok());
+    }
+}
@@ -0,0 +1,790 @@
+use crate::utils::{Parameters, NttPoly, RingPoly, CoeffWord, WireFormat, check_coeffs, compress, decompress, coeff_bits, pack_coeffs, unpack_coeffs, serialize_coeffs, deserialize_coeffs, decompress_d};
+use crate::format::{ObjectType, seal, open};
+use crate::error::RingLweError;
+use polynomial_ring::Polynomial;
+use std::collections::BTreeMap;
+
+/// Ring-LWE public key (b, a) with b = -a*sk - e
+///
+/// The key also keeps b and a in the NTT domain, so encryption only
+/// transforms the fresh randomness. Coefficients are stored in words W, u32 unless
+/// converted with `to_word`; u16 halves the memory for q < 2^16.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicKey<W: CoeffWord = u32> {
+    b: RingPoly<W>,
+    a: RingPoly<W>,
+    b_ntt: NttPoly,
+    a_ntt: NttPoly,
+    params: Parameters,
+}
+
+/// Ring-LWE secret key sk
+///
+/// The key also keeps sk in the NTT domain, so decryption only
+/// transforms the ciphertext. Coefficients are stored in words W as for `PublicKey`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretKey<W: CoeffWord = u32> {
+    s: RingPoly<W>,
+    s_ntt: NttPoly,
+    params: Parameters,
+}
+
+/// Tail factor turning a noise standard deviation into a bound on its largest coefficient
+const NOISE_TAIL: f64 = 8.0;
+
+/// Bytes of the noise estimate, an f64, at the end of a packed ciphertext
+const NOISE_LEN: usize = 8;
+
+/// Noise estimate for a ciphertext of unknown history: the largest standard deviation
+/// whose tail bound still fits under q/(2t), i.e. an estimated noise budget of 0
+fn unknown_noise(params: &Parameters) -> f64 {
+    params.q as f64 / (2.0 * params.t as f64) / NOISE_TAIL
+}
+
+/// Ring-LWE ciphertext (c0, c1)
+///
+/// The ciphertext also carries a heuristic estimate of the standard deviation of its
+/// noise, which homomorphic operations update. Coefficients are stored in words W,
+/// the word of the public key that encrypted it.
+#[derive(Debug, Clone)]
+pub struct Ciphertext<W: CoeffWord = u32> {
+    c0: RingPoly<W>,
+    c1: RingPoly<W>,
+    noise: f64,
+    params: Parameters,
+}
+
+/// Ciphertexts are equal when their components are; the noise estimate is bookkeeping
+impl<W: CoeffWord> PartialEq for Ciphertext<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.c0 == other.c0 && self.c1 == other.c1 && self.params == other.params
+    }
+}
+
+/// Ring-LWE ciphertext (c0, c1) with c0 rounded to dv bits and c1 rounded to du bits per coefficient
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedCiphertext {
+    c0: Vec<u32>,
+    c1: Vec<u32>,
+    du: u32,
+    dv: u32,
+    params: Parameters,
+}
+
+/// Three-component ciphertext (c0, c1, c2) decrypting as c0 + c1*sk + c2*sk^2,
+/// the result of a ciphertext multiplication before relinearization
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ciphertext3<W: CoeffWord = u32> {
+    c0: RingPoly<W>,
+    c1: RingPoly<W>,
+    c2: RingPoly<W>,
+    noise: f64,
+    params: Parameters,
+}
+
+/// Relinearization key: for each base-T digit i of q, an encryption
+/// (-a_i*sk - e_i + T^i*sk^2, a_i) stored in the NTT domain
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelinKey {
+    base: u32,
+    b_ntt: Vec<NttPoly>,
+    a_ntt: Vec<NttPoly>,
+    params: Parameters,
+}
+
+/// Galois keys: for each Galois element k and each base-T digit i of q, an encryption
+/// (-a_i*sk - e_i + T^i*sk(x^k), a_i) stored in the NTT domain
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaloisKeys {
+    base: u32,
+    keys: BTreeMap<usize, (Vec<NttPoly>, Vec<NttPoly>)>,
+    params: Parameters,
+}
+
+impl<W: CoeffWord> PublicKey<W> {
+    /// Create a public key from its two polynomials in Z_q[X]/(x^n+1)
+    /// # Arguments:
+    /// * `b` - polynomial b = -a*sk - e
+    /// * `a` - uniform polynomial a
+    /// * `params` - ring-LWE parameters the key was generated under
+    pub fn from_ring(b: RingPoly<W>, a: RingPoly<W>, params: &Parameters) -> Self {
+        let ntt = params.ntt();
+        let (b_ntt, a_ntt) = (b.to_ntt(&ntt), a.to_ntt(&ntt));
+        PublicKey { b, a, b_ntt, a_ntt, params: params.clone() }
+    }
+
+    /// Polynomial b of the public key
+    pub fn b(&self) -> &RingPoly<W> {
+        &self.b
+    }
+
+    /// Polynomial a of the public key
+    pub fn a(&self) -> &RingPoly<W> {
+        &self.a
+    }
+
+    /// Polynomial b of the public key in the NTT domain
+    pub fn b_ntt(&self) -> &NttPoly {
+        &self.b_ntt
+    }
+
+    /// Polynomial a of the public key in the NTT domain
+    pub fn a_ntt(&self) -> &NttPoly {
+        &self.a_ntt
+    }
+
+    /// Parameters the key was generated under
+    pub fn params(&self) -> &Parameters {
+        &self.params
+    }
+
+    /// Convert into the array form (b, a) used by the free functions
+    pub fn into_array(self) -> [Polynomial<i64>; 2] {
+        [self.b.to_poly(), self.a.to_poly()]
+    }
+
+    /// Centered coefficients of b followed by a, n of each
+    pub fn to_coeffs(&self) -> Vec<i64> {
+        let mut coeffs = self.b.to_centered();
+        coeffs.extend(self.a.to_centered());
+        coeffs
+    }
+
+    /// Encode the public key as a base64 string
+    pub fn to_base64(&self) -> String {
+        compress(&self.to_coeffs())
+    }
+
+    /// Bit-pack b followed by a, ceil(log2 q) bits per coefficient
+    pub fn to_packed(&self) -> Vec<u8> {
+        let q = self.params.q;
+        pack_coeffs(&self.to_coeffs(), q, coeff_bits(q))
+    }
+
+    /// Encode the public key as a base64 container with a payload in the given wire format
+    pub fn encode(&self, format: WireFormat) -> String {
+        let payload = match format {
+            WireFormat::Bincode => serialize_coeffs(&self.to_coeffs()),
+            WireFormat::Packed => self.to_packed(),
+        };
+        seal(ObjectType::PublicKey, &self.params, format, &payload)
+    }
+
+    /// Convert the key to another coefficient word
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, _) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let small = pk.to_word::<u16>();
+    /// assert_eq!(small.to_coeffs(), pk.to_coeffs());
+    /// ```
+    pub fn to_word<V: CoeffWord>(&self) -> PublicKey<V> {
+        PublicKey { b: self.b.to_word(), a: self.a.to_word(), b_ntt: self.b_ntt.clone(), a_ntt: self.a_ntt.clone(), params: self.params.clone() }
+    }
+}
+
+/// Constructors from untyped data, which produce u32 words
+impl PublicKey {
+    /// Create a public key from its two polynomials
+    /// # Arguments:
+    /// * `b` - polynomial b = -a*sk - e
+    /// * `a` - uniform polynomial a
+    /// * `params` - ring-LWE parameters the key was generated under
+    pub fn new(b: Polynomial<i64>, a: Polynomial<i64>, params: &Parameters) -> Self {
+        let (n, q) = (params.n, params.q);
+        PublicKey::from_ring(RingPoly::from_poly(&b, n, q), RingPoly::from_poly(&a, n, q), params)
+    }
+
+    /// Parse a public key from the coefficients of b followed by a
+    /// # Arguments:
+    /// * `coeffs` - 2n coefficients in [-q/2, q/2]
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// public key, or an error if the length or a coefficient is invalid
+    pub fn from_coeffs(coeffs: &[i64], params: &Parameters) -> Result<Self, RingLweError> {
+        let n = params.n;
+        if coeffs.len() != 2 * n {
+            return Err(RingLweError::LengthMismatch { expected: 2 * n, found: coeffs.len() });
+        }
+        check_coeffs(coeffs, params.q)?;
+        let b = Polynomial::new(coeffs[..n].to_vec());
+        let a = Polynomial::new(coeffs[n..].to_vec());
+        Ok(PublicKey::new(b, a, params))
+    }
+
+    /// Load a public key from a base64 string
+    /// # Arguments:
+    /// * `pk_base64` - public key as a base64 encoded string
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// public key, or an error if the string is malformed
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, _) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let loaded = ring_lwe::types::PublicKey::from_base64(&pk.to_base64(), &params).unwrap();
+    /// assert_eq!(pk, loaded);
+    /// assert!(ring_lwe::types::PublicKey::from_base64("not a key", &params).is_err());
+    /// ```
+    pub fn from_base64(pk_base64: &str, params: &Parameters) -> Result<Self, RingLweError> {
+        PublicKey::from_coeffs(&decompress(pk_base64)?, params)
+    }
+
+    /// Unpack a public key written by `to_packed`
+    /// # Arguments:
+    /// * `bytes` - packed coefficients of b followed by a
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// public key, or an error if the length or a coefficient is invalid
+    pub fn from_packed(bytes: &[u8], params: &Parameters) -> Result<Self, RingLweError> {
+        let q = params.q;
+        PublicKey::from_coeffs(&unpack_coeffs(bytes, 2 * params.n, q, coeff_bits(q))?, params)
+    }
+
+    /// Load a public key from a base64 container, or from a bare payload in the given wire format
+    /// # Arguments:
+    /// * `pk_base64` - public key as a base64 encoded string
+    /// * `params` - ring-LWE parameters
+    /// * `format` - wire format of a bare payload
+    /// # Returns:
+    /// public key, or an error if the string is malformed or was written under other parameters
+    /// # Example:
+    /// ```
+    /// use ring_lwe::utils::{Parameters, WireFormat};
+    /// let params = Parameters::default();
+    /// let (pk, _) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let encoded = pk.encode(WireFormat::Packed);
+    /// assert_eq!(ring_lwe::types::PublicKey::decode(&encoded, &params, WireFormat::Bincode).unwrap(), pk);
+    /// let other = Parameters::builder().n(512).build().unwrap();
+    /// assert!(ring_lwe::types::PublicKey::decode(&encoded, &other, WireFormat::Packed).is_err());
+    /// ```
+    pub fn decode(pk_base64: &str, params: &Parameters, format: WireFormat) -> Result<Self, RingLweError> {
+        match open(pk_base64, ObjectType::PublicKey, params, format)? {
+            (WireFormat::Bincode, _, payload) => PublicKey::from_coeffs(&deserialize_coeffs(&payload)?, params),
+            (WireFormat::Packed, _, payload) => PublicKey::from_packed(&payload, params),
+        }
+    }
+}
+
+impl<W: CoeffWord> SecretKey<W> {
+    /// Create a secret key from its polynomial in Z_q[X]/(x^n+1)
+    /// # Arguments:
+    /// * `s` - secret polynomial
+    /// * `params` - ring-LWE parameters the key was generated under
+    pub fn from_ring(s: RingPoly<W>, params: &Parameters) -> Self {
+        let s_ntt = s.to_ntt(&params.ntt());
+        SecretKey { s, s_ntt, params: params.clone() }
+    }
+
+    /// Secret polynomial
+    pub fn s(&self) -> &RingPoly<W> {
+        &self.s
+    }
+
+    /// Secret polynomial in the NTT domain
+    pub fn s_ntt(&self) -> &NttPoly {
+        &self.s_ntt
+    }
+
+    /// Parameters the key was generated under
+    pub fn params(&self) -> &Parameters {
+        &self.params
+    }
+
+    /// Convert into the bare secret polynomial used by the free functions
+    pub fn into_poly(self) -> Polynomial<i64> {
+        self.s.to_poly()
+    }
+
+    /// Centered coefficients of the secret polynomial, n of them
+    pub fn to_coeffs(&self) -> Vec<i64> {
+        self.s.to_centered()
+    }
+
+    /// Encode the secret key as a base64 string
+    pub fn to_base64(&self) -> String {
+        compress(&self.to_coeffs())
+    }
+
+    /// Bit-pack the secret polynomial, 2 bits per coefficient when it is ternary
+    /// and ceil(log2 q) bits otherwise
+    pub fn to_packed(&self) -> Vec<u8> {
+        let coeffs = self.to_coeffs();
+        if coeffs.iter().all(|c| c.abs() <= 1) {
+            pack_coeffs(&coeffs, 3, 2)
+        } else {
+            pack_coeffs(&coeffs, self.params.q, coeff_bits(self.params.q))
+        }
+    }
+
+    /// Encode the secret key as a base64 container with a payload in the given wire format
+    pub fn encode(&self, format: WireFormat) -> String {
+        let payload = match format {
+            WireFormat::Bincode => serialize_coeffs(&self.to_coeffs()),
+            WireFormat::Packed => self.to_packed(),
+        };
+        seal(ObjectType::SecretKey, &self.params, format, &payload)
+    }
+
+    /// Convert the key to another coefficient word
+    pub fn to_word<V: CoeffWord>(&self) -> SecretKey<V> {
+        SecretKey { s: self.s.to_word(), s_ntt: self.s_ntt.clone(), params: self.params.clone() }
+    }
+}
+
+/// Constructors from untyped data, which produce u32 words
+impl SecretKey {
+    /// Create a secret key from its polynomial
+    /// # Arguments:
+    /// * `s` - secret polynomial
+    /// * `params` - ring-LWE parameters the key was generated under
+    pub fn new(s: Polynomial<i64>, params: &Parameters) -> Self {
+        SecretKey::from_ring(RingPoly::from_poly(&s, params.n, params.q), params)
+    }
+
+    /// Parse a secret key from its coefficients
+    /// # Arguments:
+    /// * `coeffs` - n coefficients in [-q/2, q/2]
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// secret key, or an error if the length or a coefficient is invalid
+    pub fn from_coeffs(coeffs: &[i64], params: &Parameters) -> Result<Self, RingLweError> {
+        if coeffs.len() != params.n {
+            return Err(RingLweError::LengthMismatch { expected: params.n, found: coeffs.len() });
+        }
+        check_coeffs(coeffs, params.q)?;
+        Ok(SecretKey::new(Polynomial::new(coeffs.to_vec()), params))
+    }
+
+    /// Load a secret key from a base64 string
+    /// # Arguments:
+    /// * `sk_base64` - secret key as a base64 encoded string
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// secret key, or an error if the string is malformed
+    pub fn from_base64(sk_base64: &str, params: &Parameters) -> Result<Self, RingLweError> {
+        SecretKey::from_coeffs(&decompress(sk_base64)?, params)
+    }
+
+    /// Unpack a secret key written by `to_packed`, telling the two widths apart by length
+    /// # Arguments:
+    /// * `bytes` - packed coefficients of the secret polynomial
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// secret key, or an error if the length or a coefficient is invalid
+    pub fn from_packed(bytes: &[u8], params: &Parameters) -> Result<Self, RingLweError> {
+        let (n, q) = (params.n, params.q);
+        let coeffs = if bytes.len() == (2 * n).div_ceil(8) {
+            unpack_coeffs(bytes, n, 3, 2)?
+        } else {
+            unpack_coeffs(bytes, n, q, coeff_bits(q))?
+        };
+        SecretKey::from_coeffs(&coeffs, params)
+    }
+
+    /// Load a secret key from a base64 container, or from a bare payload in the given wire format
+    /// # Arguments:
+    /// * `sk_base64` - secret key as a base64 encoded string
+    /// * `params` - ring-LWE parameters
+    /// * `format` - wire format of a bare payload
+    /// # Returns:
+    /// secret key, or an error if the string is malformed or was written under other parameters
+    pub fn decode(sk_base64: &str, params: &Parameters, format: WireFormat) -> Result<Self, RingLweError> {
+        match open(sk_base64, ObjectType::SecretKey, params, format)? {
+            (WireFormat::Bincode, _, payload) => SecretKey::from_coeffs(&deserialize_coeffs(&payload)?, params),
+            (WireFormat::Packed, _, payload) => SecretKey::from_packed(&payload, params),
+        }
+    }
+}
+
+impl<W: CoeffWord> Ciphertext<W> {
+    /// Create a ciphertext from its two polynomials in Z_q[X]/(x^n+1)
+    ///
+    /// Nothing is known about the noise of the polynomials, so the noise estimate
+    /// conservatively leaves an estimated budget of 0.
+    /// # Arguments:
+    /// * `c0` - ciphertext component 0
+    /// * `c1` - ciphertext component 1
+    /// * `params` - ring-LWE parameters used for encryption
+    pub fn from_ring(c0: RingPoly<W>, c1: RingPoly<W>, params: &Parameters) -> Self {
+        Ciphertext { c0, c1, noise: unknown_noise(params), params: params.clone() }
+    }
+
+    /// Replace the noise estimate
+    pub(crate) fn with_noise(mut self, noise: f64) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Heuristic standard deviation of the noise coefficients
+    pub fn noise_estimate(&self) -> f64 {
+        self.noise
+    }
+
+    /// Heuristic noise budget in bits, from the noise estimate rather than the secret key
+    /// # Returns:
+    /// estimated headroom, at most `decrypt::noise_budget` plus a bit or two for fresh
+    /// ciphertexts; 0 means the ciphertext may no longer decrypt
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let ct = pk.encrypt(&polynomial_ring::Polynomial::new(vec![1]), None);
+    /// assert!(ct.estimated_noise_budget() > 3.0);
+    /// assert!(ct.estimated_noise_budget() <= sk.noise_budget(&ct).unwrap());
+    /// ```
+    pub fn estimated_noise_budget(&self) -> f64 {
+        self.params.headroom_bits(NOISE_TAIL * self.noise)
+    }
+
+    /// Ciphertext component 0
+    pub fn c0(&self) -> &RingPoly<W> {
+        &self.c0
+    }
+
+    /// Ciphertext component 1
+    pub fn c1(&self) -> &RingPoly<W> {
+        &self.c1
+    }
+
+    /// Parameters used for encryption
+    pub fn params(&self) -> &Parameters {
+        &self.params
+    }
+
+    /// Convert into the array form (c0, c1) used by the free functions
+    pub fn into_array(self) -> [Polynomial<i64>; 2] {
+        [self.c0.to_poly(), self.c1.to_poly()]
+    }
+
+    /// Centered coefficients of c0 followed by c1, n of each
+    pub fn to_coeffs(&self) -> Vec<i64> {
+        let mut coeffs = self.c0.to_centered();
+        coeffs.extend(self.c1.to_centered());
+        coeffs
+    }
+
+    /// Bit-pack c0 followed by c1, ceil(log2 q) bits per coefficient, then the noise estimate
+    /// as a little-endian f64
+    pub fn to_packed(&self) -> Vec<u8> {
+        let q = self.params.q;
+        let mut bytes = pack_coeffs(&self.to_coeffs(), q, coeff_bits(q));
+        bytes.extend_from_slice(&self.noise.to_le_bytes());
+        bytes
+    }
+
+    /// Convert the ciphertext to another coefficient word, keeping its noise estimate
+    pub fn to_word<V: CoeffWord>(&self) -> Ciphertext<V> {
+        Ciphertext { c0: self.c0.to_word(), c1: self.c1.to_word(), noise: self.noise, params: self.params.clone() }
+    }
+}
+
+/// Constructors from untyped data, which produce u32 words
+impl Ciphertext {
+    /// Create a ciphertext from its two polynomials, with the noise estimate of `from_ring`
+    /// # Arguments:
+    /// * `c0` - ciphertext component 0
+    /// * `c1` - ciphertext component 1
+    /// * `params` - ring-LWE parameters used for encryption
+    pub fn new(c0: Polynomial<i64>, c1: Polynomial<i64>, params: &Parameters) -> Self {
+        let (n, q) = (params.n, params.q);
+        Ciphertext::from_ring(RingPoly::from_poly(&c0, n, q), RingPoly::from_poly(&c1, n, q), params)
+    }
+
+    /// Parse a ciphertext from the coefficients of c0 followed by c1, with the noise estimate of `from_ring`
+    /// # Arguments:
+    /// * `coeffs` - 2n coefficients in [-q/2, q/2]
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// ciphertext, or an error if the length or a coefficient is invalid
+    pub fn from_coeffs(coeffs: &[i64], params: &Parameters) -> Result<Self, RingLweError> {
+        let n = params.n;
+        if coeffs.len() != 2 * n {
+            return Err(RingLweError::LengthMismatch { expected: 2 * n, found: coeffs.len() });
+        }
+        check_coeffs(coeffs, params.q)?;
+        let c0 = Polynomial::new(coeffs[..n].to_vec());
+        let c1 = Polynomial::new(coeffs[n..].to_vec());
+        Ok(Ciphertext::new(c0, c1, params))
+    }
+
+    /// Unpack a ciphertext written by `to_packed`, telling it apart by length from the packed
+    /// coefficients alone, as written before the noise estimate was stored
+    /// # Arguments:
+    /// * `bytes` - packed coefficients of c0 followed by c1, optionally followed by the noise estimate
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// ciphertext, with the noise estimate of `from_ring` if none was stored, or an error if
+    /// the length, a coefficient or the noise estimate is invalid
+    /// # Example:
+    /// ```
+    /// use polynomial_ring::Polynomial;
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let (pk, sk) = ring_lwe::types::SecretKey::generate(&params, None);
+    /// let ct = ring_lwe::evaluate::add_plain(&pk.encrypt(&Polynomial::new(vec![1]), None), &Polynomial::new(vec![0, 1]));
+    /// let loaded = ring_lwe::types::Ciphertext::from_packed(&ct.to_packed(), &params).unwrap();
+    /// assert_eq!(loaded.noise_estimate(), ct.noise_estimate());
+    /// ```
+    pub fn from_packed(bytes: &[u8], params: &Parameters) -> Result<Self, RingLweError> {
+        let q = params.q;
+        let coeffs_len = Ciphertext::packed_coeffs_len(params);
+        if bytes.len() == coeffs_len {
+            return Ciphertext::from_coeffs(&unpack_coeffs(bytes, 2 * params.n, q, coeff_bits(q))?, params);
+        }
+        let expected = Ciphertext::packed_len(params);
+        if bytes.len() != expected {
+            return Err(RingLweError::LengthMismatch { expected, found: bytes.len() });
+        }
+        let noise = f64::from_le_bytes(bytes[coeffs_len..].try_into().unwrap());
+        if !(noise.is_finite() && noise >= 0.0) {
+            return Err(RingLweError::Decode(format!("invalid noise estimate {}", noise)));
+        }
+        let ct = Ciphertext::from_coeffs(&unpack_coeffs(&bytes[..coeffs_len], 2 * params.n, q, coeff_bits(q))?, params)?;
+        Ok(ct.with_noise(noise))
+    }
+
+    /// Size in bytes of a packed ciphertext under the given parameters
+    pub fn packed_len(params: &Parameters) -> usize {
+        Ciphertext::packed_coeffs_len(params) + NOISE_LEN
+    }
+
+    /// Size in bytes of the packed coefficients of a ciphertext, without the noise estimate
+    pub fn packed_coeffs_len(params: &Parameters) -> usize {
+        (2 * params.n * coeff_bits(params.q) as usize).div_ceil(8)
+    }
+}
+
+impl CompressedCiphertext {
+    /// Create a compressed ciphertext from its rounded components
+    /// # Arguments:
+    /// * `c0` - n values of ciphertext component 0, each below 2^dv
+    /// * `c1` - n values of ciphertext component 1, each below 2^du
+    /// * `du` - bits per coefficient of c1
+    /// * `dv` - bits per coefficient of c0
+    /// * `params` - ring-LWE parameters used for encryption
+    /// # Returns:
+    /// compressed ciphertext, or an error if a bit width, length or value is invalid
+    pub fn new(c0: Vec<u32>, c1: Vec<u32>, du: u32, dv: u32, params: &Parameters) -> Result<Self, RingLweError> {
+        CompressedCiphertext::check_bits(du, dv, params)?;
+        for (values, d) in [(&c0, dv), (&c1, du)] {
+            if values.len() != params.n {
+                return Err(RingLweError::LengthMismatch { expected: params.n, found: values.len() });
+            }
+            if let Some(index) = values.iter().position(|&v| v >> d != 0) {
+                return Err(RingLweError::InvalidCoefficient { index, value: values[index] as i64, modulus: 1 << d });
+            }
+        }
+        Ok(CompressedCiphertext { c0, c1, du, dv, params: params.clone() })
+    }
+
+    /// Check that du and dv lie in [1, ceil(log2 q)]
+    fn check_bits(du: u32, dv: u32, params: &Parameters) -> Result<(), RingLweError> {
+        let max_bits = coeff_bits(params.q);
+        match [du, dv].into_iter().find(|&d| d == 0 || d > max_bits) {
+            Some(d) => Err(RingLweError::InvalidParameters(format!("{} bits per coefficient is not in [1, {}]", d, max_bits))),
+            None => Ok(()),
+        }
+    }
+
+    /// Rounded ciphertext component 0, dv bits per coefficient
+    pub fn c0(&self) -> &[u32] {
+        &self.c0
+    }
+
+    /// Rounded ciphertext component 1, du bits per coefficient
+    pub fn c1(&self) -> &[u32] {
+        &self.c1
+    }
+
+    /// Bits per coefficient of c1
+    pub fn du(&self) -> u32 {
+        self.du
+    }
+
+    /// Bits per coefficient of c0
+    pub fn dv(&self) -> u32 {
+        self.dv
+    }
+
+    /// Parameters used for encryption
+    pub fn params(&self) -> &Parameters {
+        &self.params
+    }
+
+    /// Lift the rounded components back to a ciphertext in Z_q[X]/(x^n+1), estimating its
+    /// noise as that of a fresh ciphertext plus the rounding error
+    pub fn decompress(&self) -> Ciphertext {
+        let (n, q) = (self.params.n, self.params.q);
+        let lift = |values: &[u32], d: u32| {
+            let coeffs = values.iter().map(|&y| decompress_d(y, q, d)).collect();
+            RingPoly::from_poly(&Polynomial::new(coeffs), n, q)
+        };
+        // rounding to d bits adds a roughly uniform error of width q/2^d, to c1 before it meets sk
+        let rounding = |d: u32| (q as f64 / 2f64.powi(d as i32)).powi(2) / 12.0;
+        let fresh = self.params.fresh_noise_std();
+        let noise = (fresh.powi(2) + rounding(self.dv) + n as f64 * 2.0 / 3.0 * rounding(self.du)).sqrt();
+        Ciphertext::from_ring(lift(&self.c0, self.dv), lift(&self.c1, self.du), &self.params).with_noise(noise)
+    }
+
+    /// Bit-pack c0 in dv bits followed by c1 in du bits per coefficient
+    pub fn to_packed(&self) -> Vec<u8> {
+        let widen = |values: &[u32]| values.iter().map(|&v| v as i64).collect::<Vec<i64>>();
+        let mut bytes = pack_coeffs(&widen(&self.c0), 1 << self.dv, self.dv);
+        bytes.extend(pack_coeffs(&widen(&self.c1), 1 << self.du, self.du));
+        bytes
+    }
+
+    /// Unpack a compressed ciphertext written by `to_packed`
+    /// # Arguments:
+    /// * `bytes` - packed c0 followed by packed c1
+    /// * `du` - bits per coefficient of c1
+    /// * `dv` - bits per coefficient of c0
+    /// * `params` - ring-LWE parameters
+    /// # Returns:
+    /// compressed ciphertext, or an error if the length or a bit width is invalid
+    pub fn from_packed(bytes: &[u8], du: u32, dv: u32, params: &Parameters) -> Result<Self, RingLweError> {
+        let n = params.n;
+        CompressedCiphertext::check_bits(du, dv, params)?;
+        let expected = CompressedCiphertext::packed_len(params, du, dv);
+        if bytes.len() != expected {
+            return Err(RingLweError::LengthMismatch { expected, found: bytes.len() });
+        }
+        let split = (n * dv as usize).div_ceil(8);
+        let narrow = |values: Vec<i64>, d: u32| values.iter().map(|v| v.rem_euclid(1 << d) as u32).collect();
+        let c0 = narrow(unpack_coeffs(&bytes[..split], n, 1 << dv, dv)?, dv);
+        let c1 = narrow(unpack_coeffs(&bytes[split..], n, 1 << du, du)?, du);
+        CompressedCiphertext::new(c0, c1, du, dv, params)
+    }
+
+    /// Size in bytes of a packed compressed ciphertext
+    pub fn packed_len(params: &Parameters, du: u32, dv: u32) -> usize {
+        (params.n * dv as usize).div_ceil(8) + (params.n * du as usize).div_ceil(8)
+    }
+}
+
+impl<W: CoeffWord> Ciphertext3<W> {
+    /// Create a three-component ciphertext from its polynomials in Z_q[X]/(x^n+1), with a noise
+    /// estimate that leaves an estimated budget of 0 as for `Ciphertext::from_ring`
+    /// # Arguments:
+    /// * `c0` - ciphertext component 0
+    /// * `c1` - ciphertext component 1, multiplied by sk on decryption
+    /// * `c2` - ciphertext component 2, multiplied by sk^2 on decryption
+    /// * `params` - ring-LWE parameters used for encryption
+    pub fn from_ring(c0: RingPoly<W>, c1: RingPoly<W>, c2: RingPoly<W>, params: &Parameters) -> Self {
+        Ciphertext3 { c0, c1, c2, noise: unknown_noise(params), params: params.clone() }
+    }
+
+    /// Replace the noise estimate
+    pub(crate) fn with_noise(mut self, noise: f64) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Heuristic standard deviation of the noise coefficients of c0 + c1*sk + c2*sk^2
+    pub fn noise_estimate(&self) -> f64 {
+        self.noise
+    }
+
+    /// Ciphertext component 0
+    pub fn c0(&self) -> &RingPoly<W> {
+        &self.c0
+    }
+
+    /// Ciphertext component 1
+    pub fn c1(&self) -> &RingPoly<W> {
+        &self.c1
+    }
+
+    /// Ciphertext component 2
+    pub fn c2(&self) -> &RingPoly<W> {
+        &self.c2
+    }
+
+    /// Parameters used for encryption
+    pub fn params(&self) -> &Parameters {
+        &self.params
+    }
+}
+
+impl RelinKey {
+    /// Create a relinearization key from its components in the NTT domain
+    /// # Arguments:
+    /// * `base` - digit base T
+    /// * `b_ntt` - polynomials -a_i*sk - e_i + T^i*sk^2, one per digit
+    /// * `a_ntt` - uniform polynomials a_i, one per digit
+    /// * `params` - ring-LWE parameters the key was generated under
+    pub fn from_ntt(base: u32, b_ntt: Vec<NttPoly>, a_ntt: Vec<NttPoly>, params: &Parameters) -> Self {
+        assert_eq!(b_ntt.len(), a_ntt.len(), "relinearization key components differ in length");
+        RelinKey { base, b_ntt, a_ntt, params: params.clone() }
+    }
+
+    /// Number of base-T digits needed for coefficients in [0, q)
+    pub fn digits(base: u32, q: i64) -> usize {
+        let (mut digits, mut range) = (1, base as i64);
+        while range < q {
+            range = range.saturating_mul(base as i64);
+            digits += 1;
+        }
+        digits
+    }
+
+    /// Digit base T
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// Polynomials b_i in the NTT domain
+    pub fn b_ntt(&self) -> &[NttPoly] {
+        &self.b_ntt
+    }
+
+    /// Polynomials a_i in the NTT domain
+    pub fn a_ntt(&self) -> &[NttPoly] {
+        &self.a_ntt
+    }
+
+    /// Parameters the key was generated under
+    pub fn params(&self) -> &Parameters {
+        &self.params
+    }
+}
+
+impl GaloisKeys {
+    /// Create Galois keys from their components in the NTT domain
+    /// # Arguments:
+    /// * `base` - digit base T
+    /// * `keys` - for each Galois element k, the polynomials b_i = -a_i*sk - e_i + T^i*sk(x^k) and a_i
+    /// * `params` - ring-LWE parameters the keys were generated under
+    pub fn from_ntt(base: u32, keys: BTreeMap<usize, (Vec<NttPoly>, Vec<NttPoly>)>, params: &Parameters) -> Self {
+        for (b_ntt, a_ntt) in keys.values() {
+            assert_eq!(b_ntt.len(), a_ntt.len(), "Galois key components differ in length");
+        }
+        GaloisKeys { base, keys, params: params.clone() }
+    }
+
+    /// Digit base T
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// Galois elements k that have a key, in increasing order
+    pub fn elements(&self) -> Vec<usize> {
+        self.keys.keys().copied().collect()
+    }
+
+    /// Whether there is a key for the Galois element k
+    pub fn contains(&self, k: usize) -> bool {
+        self.keys.contains_key(&k)
+    }
+
+    /// Polynomials (b_i, a_i) in the NTT domain of the key for the Galois element k
+    pub fn get(&self, k: usize) -> Option<(&[NttPoly], &[NttPoly])> {
+        self.keys.get(&k).map(|(b_ntt, a_ntt)| (b_ntt.as_slice(), a_ntt.as_slice()))
+    }
+
+    /// Parameters the keys were generated under
+    pub fn params(&self) -> &Parameters {
+        &self.params
+    }
+}
+
@@ -1,43 +1,425 @@
 use polynomial_ring::Polynomial;
 use rand_distr::{Uniform, Normal, Distribution};
 use ntt::polymul_ntt;
-use rand::SeedableRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
 use rand::rngs::StdRng;
 use base64::{engine::general_purpose, Engine as _};
-use bincode;
+use crate::error::RingLweError;
+use crate::reduction::{Barrett16, Barrett32, Reducer};
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::ops::{Add, Mul, Neg, Sub};
 
 /// Ring-LWE parameters
-#[derive(Debug)]
+///
+/// The ciphertext modulus q must be a prime below 2^31 with q = 1 mod 2n: the NTT
+/// reduces its products with `Montgomery32`, which needs an odd modulus below 2^31,
+/// or with `Montgomery16` when q < 2^15.
+#[derive(Clone)]
 pub struct Parameters {
     pub n: usize,       // Polynomial modulus degree
     pub q: i64,       // Ciphertext modulus
     pub t: i64,       // Plaintext modulus
-    pub omega: i64,   // n-th root of unity mod q
+    pub omega: i64,   // primitive 2n-th root of unity mod q
     pub f: Polynomial<i64>, // Polynomial modulus (x^n + 1 representation)
-    #[allow(dead_code)]
-    pub sigma: f64,    // Standard deviation for normal distribution
+    pub sigma: f64,    // Standard deviation for Gaussian error distributions
+    pub error_dist: ErrorDistribution, // Distribution of the error polynomials e, e1, e2
+    pub modulus_chain: Vec<i64>, // Smaller ciphertext moduli to switch down to, largest first
+    ntt: Arc<NttContext>, // NTT tables for (n, q, omega), shared between clones
+    dgs: Option<Arc<DiscreteGaussianSampler>>, // CDT for a discrete Gaussian error_dist, shared between clones
+    tensor_ntt: Arc<OnceLock<Vec<NttContext>>>, // NTT tables for the tensor product primes, built on first use
+}
+
+/// Parameters are equal when their public fields are; the NTT tables are derived data
+impl PartialEq for Parameters {
+    fn eq(&self, other: &Self) -> bool {
+        self.n == other.n && self.q == other.q && self.t == other.t && self.omega == other.omega
+            && self.f == other.f && self.sigma == other.sigma && self.error_dist == other.error_dist
+            && self.modulus_chain == other.modulus_chain
+    }
+}
+
+/// Debug output omits the NTT tables
+impl fmt::Debug for Parameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parameters")
+            .field("n", &self.n)
+            .field("q", &self.q)
+            .field("t", &self.t)
+            .field("omega", &self.omega)
+            .field("sigma", &self.sigma)
+            .field("error_dist", &self.error_dist)
+            .field("modulus_chain", &self.modulus_chain)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Distribution used to sample error polynomials
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorDistribution {
+    /// Uniform over {-1, 0, 1}
+    Ternary,
+    /// Continuous Gaussian with standard deviation sigma, rounded to the nearest integer
+    RoundedGaussian,
+    /// Discrete Gaussian with standard deviation sigma, cut off at tail_cut * sigma with tail_cut <= 20
+    DiscreteGaussian { tail_cut: f64 },
+    /// Centered binomial distribution with parameter eta, supported on [-eta, eta]
+    CenteredBinomial { eta: u32 },
+}
+
+impl ErrorDistribution {
+    /// Variance of a single error coefficient
+    /// # Arguments:
+    /// * `sigma` - standard deviation of the Gaussian distributions
+    pub fn variance(&self, sigma: f64) -> f64 {
+        match self {
+            ErrorDistribution::Ternary => 2.0 / 3.0,
+            // rounding adds an independent, roughly uniform error in [-1/2, 1/2]
+            ErrorDistribution::RoundedGaussian => sigma * sigma + 1.0 / 12.0,
+            ErrorDistribution::DiscreteGaussian { .. } => sigma * sigma,
+            ErrorDistribution::CenteredBinomial { eta } => *eta as f64 / 2.0,
+        }
+    }
 }
 
 /// Default parameters for ring-LWE
 impl Default for Parameters {
     fn default() -> Self {
-        let n = 1024;
-        let q = 12289;
-        let t = 2;
+        ParametersBuilder::default().build().expect("default parameters are valid")
+    }
+}
+
+/// Checked builder for ring-LWE parameters
+/// # Example:
+/// ```
+/// let params = ring_lwe::utils::Parameters::builder().n(512).build().unwrap();
+/// assert_eq!(params.f.coeffs().len(), 513);
+/// assert!(ring_lwe::utils::Parameters::builder().q(12288).build().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParametersBuilder {
+    n: usize,
+    q: i64,
+    t: i64,
+    sigma: f64,
+    error_dist: ErrorDistribution,
+    modulus_chain: Vec<i64>,
+}
+
+/// Builder starting from the default parameter set
+impl Default for ParametersBuilder {
+    fn default() -> Self {
+        ParametersBuilder { n: 1024, q: 12289, t: 2, sigma: 8.0, error_dist: ErrorDistribution::Ternary, modulus_chain: vec![] }
+    }
+}
+
+impl ParametersBuilder {
+    /// Set the polynomial modulus degree (a power of two)
+    pub fn n(mut self, n: usize) -> Self {
+        self.n = n;
+        self
+    }
+
+    /// Set the ciphertext modulus (a prime below 2^31 with q = 1 mod 2n)
+    pub fn q(mut self, q: i64) -> Self {
+        self.q = q;
+        self
+    }
+
+    /// Set the plaintext modulus (2 <= t < q)
+    pub fn t(mut self, t: i64) -> Self {
+        self.t = t;
+        self
+    }
+
+    /// Set the standard deviation of the Gaussian error distributions
+    pub fn sigma(mut self, sigma: f64) -> Self {
+        self.sigma = sigma;
+        self
+    }
+
+    /// Set the distribution of the error polynomials
+    pub fn error_distribution(mut self, error_dist: ErrorDistribution) -> Self {
+        self.error_dist = error_dist;
+        self
+    }
+
+    /// Set the smaller moduli that ciphertexts can be switched down to, largest first
+    /// (primes with t < q_i < q and q_i = 1 mod 2n)
+    pub fn modulus_chain(mut self, modulus_chain: &[i64]) -> Self {
+        self.modulus_chain = modulus_chain.to_vec();
+        self
+    }
+
+    /// Validate the parameters and derive omega and f
+    /// # Returns:
+    /// parameters, or an error naming the first violated invariant
+    pub fn build(self) -> Result<Parameters, RingLweError> {
+        let (n, q, t, sigma, error_dist, modulus_chain) = (self.n, self.q, self.t, self.sigma, self.error_dist, self.modulus_chain);
+        if n < 2 || !n.is_power_of_two() {
+            return Err(RingLweError::InvalidParameters(format!("n = {} is not a power of two", n)));
+        }
+        if !is_prime(q) {
+            return Err(RingLweError::InvalidParameters(format!("q = {} is not prime", q)));
+        }
+        // the NTT tables use Montgomery32 arithmetic
+        if q >= 1 << 31 {
+            return Err(RingLweError::InvalidParameters(format!("q = {} is not below 2^31", q)));
+        }
+        if (q - 1) % (2 * n as i64) != 0 {
+            return Err(RingLweError::InvalidParameters(format!("q = {} is not 1 mod 2n = {}", q, 2 * n)));
+        }
+        if t < 2 || t >= q {
+            return Err(RingLweError::InvalidParameters(format!("t = {} is not in [2, q)", t)));
+        }
+        if !(sigma > 0.0 && sigma.is_finite()) {
+            return Err(RingLweError::InvalidParameters(format!("sigma = {} is not positive", sigma)));
+        }
+        match error_dist {
+            ErrorDistribution::DiscreteGaussian { tail_cut } if !(tail_cut > 0.0 && tail_cut <= DISCRETE_GAUSSIAN_MAX_TAIL_CUT) => {
+                return Err(RingLweError::InvalidParameters(format!("tail cut {} is not in (0, {}]", tail_cut, DISCRETE_GAUSSIAN_MAX_TAIL_CUT)));
+            }
+            ErrorDistribution::DiscreteGaussian { .. } if sigma >= DISCRETE_GAUSSIAN_MAX_SIGMA => {
+                return Err(RingLweError::InvalidParameters(format!("sigma = {} is not below 2^15 for the discrete Gaussian", sigma)));
+            }
+            ErrorDistribution::CenteredBinomial { eta: 0 } => {
+                return Err(RingLweError::InvalidParameters(String::from("eta must be at least 1")));
+            }
+            _ => {}
+        }
+        let mut previous = q;
+        for &q_i in &modulus_chain {
+            if q_i >= previous || q_i <= t {
+                return Err(RingLweError::InvalidParameters(format!("modulus chain entry {} is not in (t, {})", q_i, previous)));
+            }
+            if !is_prime(q_i) || (q_i - 1) % (2 * n as i64) != 0 {
+                return Err(RingLweError::InvalidParameters(format!("modulus chain entry {} is not a prime that is 1 mod 2n = {}", q_i, 2 * n)));
+            }
+            previous = q_i;
+        }
         let omega = ntt::omega(q, 2*n);
         let mut poly_vec = vec![0i64;n+1];
         poly_vec[0] = 1;
         poly_vec[n] = 1;
         let f = Polynomial::new(poly_vec);
-        let sigma = 8.0;
-        Parameters {n, q, t, omega, f, sigma}
+        let ntt = Arc::new(NttContext::new(n, q, omega));
+        let dgs = match error_dist {
+            ErrorDistribution::DiscreteGaussian { tail_cut } => Some(Arc::new(DiscreteGaussianSampler::new(sigma, tail_cut))),
+            _ => None,
+        };
+        Ok(Parameters {n, q, t, omega, f, sigma, error_dist, modulus_chain, ntt, dgs, tensor_ntt: Arc::default()})
+    }
+}
+
+/// Number of auxiliary primes for the tensor product; their product exceeds 2^92, more than
+/// twice the n*q^2/2 bound on its coefficients for any q below 2^31 and n below 2^30
+const TENSOR_PRIMES: usize = 3;
+
+/// NTT tables for the `TENSOR_PRIMES` largest primes p < 2^31 with p = 1 mod 2n
+fn tensor_ntt_tables(n: usize) -> Vec<NttContext> {
+    let step = 2 * n as i64;
+    let tables = (1..(1i64 << 31) / step)
+        .rev()
+        .map(|k| k * step + 1)
+        .filter(|&p| is_prime(p))
+        .take(TENSOR_PRIMES)
+        .map(|p| NttContext::new(n, p, ntt::omega(p, 2 * n)))
+        .collect::<Vec<NttContext>>();
+    assert_eq!(tables.len(), TENSOR_PRIMES, "too few primes below 2^31 are 1 mod 2n = {}", step);
+    tables
+}
+
+/// Deterministic Miller-Rabin primality test for 64-bit integers
+/// # Arguments:
+/// * `p` - integer to test
+/// # Returns:
+/// true if `p` is prime
+pub fn is_prime(p: i64) -> bool {
+    if p < 2 {
+        return false;
+    }
+    let p = p as u64;
+    let mul_mod = |a: u64, b: u64| ((a as u128 * b as u128) % p as u128) as u64;
+    let pow_mod = |mut base: u64, mut exp: u64| {
+        let mut result = 1u64;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = mul_mod(result, base);
+            }
+            base = mul_mod(base, base);
+            exp >>= 1;
+        }
+        result
+    };
+    // these bases are sufficient for every 64-bit integer
+    let bases = [2u64, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    for &a in bases.iter() {
+        if p == a {
+            return true;
+        }
+        if p.is_multiple_of(a) {
+            return false;
+        }
+    }
+    let mut d = p - 1;
+    let mut r = 0;
+    while d.is_multiple_of(2) {
+        d /= 2;
+        r += 1;
+    }
+    'witness: for &a in bases.iter() {
+        let mut x = pow_mod(a, d);
+        if x == 1 || x == p - 1 {
+            continue;
+        }
+        for _ in 1..r {
+            x = mul_mod(x, x);
+            if x == p - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+impl Parameters {
+    /// Start building a checked parameter set from the defaults
+    pub fn builder() -> ParametersBuilder {
+        ParametersBuilder::default()
+    }
+
+    /// NTT tables for the ring Z_q[X]/(x^n+1)
+    /// # Returns:
+    /// the tables cached when the parameters were built, or freshly computed ones
+    /// if `n`, `q` or `omega` have been changed since
+    pub fn ntt(&self) -> Cow<'_, NttContext> {
+        let ntt = self.ntt.as_ref();
+        if ntt.n() == self.n && ntt.q() == self.q && ntt.psi() == self.omega {
+            Cow::Borrowed(ntt)
+        } else {
+            Cow::Owned(NttContext::from_params(self))
+        }
+    }
+
+    /// Discrete Gaussian sampler for the error distribution
+    /// # Returns:
+    /// the sampler cached when the parameters were built, or a freshly built one if `sigma`
+    /// or `error_dist` have been changed since; `None` unless `error_dist` is `DiscreteGaussian`
+    pub fn discrete_gaussian(&self) -> Option<Cow<'_, DiscreteGaussianSampler>> {
+        let ErrorDistribution::DiscreteGaussian { tail_cut } = self.error_dist else {
+            return None;
+        };
+        match self.dgs.as_deref() {
+            Some(dgs) if dgs.sigma == self.sigma && dgs.tail_cut == tail_cut => Some(Cow::Borrowed(dgs)),
+            _ => Some(Cow::Owned(DiscreteGaussianSampler::new(self.sigma, tail_cut))),
+        }
+    }
+
+    /// NTT tables for the auxiliary primes of the exact tensor product in `evaluate::multiply`
+    /// # Returns:
+    /// tables for the three largest primes below 2^31 that are 1 mod 2n, built on
+    /// first use and shared between clones, or freshly computed ones if `n` has been changed since
+    pub fn tensor_ntt(&self) -> Cow<'_, [NttContext]> {
+        let tables = self.tensor_ntt.get_or_init(|| tensor_ntt_tables(self.n));
+        if tables[0].n() == self.n {
+            Cow::Borrowed(tables)
+        } else {
+            Cow::Owned(tensor_ntt_tables(self.n))
+        }
+    }
+
+    /// Heuristic standard deviation of the noise coefficients of a fresh ciphertext
+    ///
+    /// Decrypting c = (b*u + e1 + m*q/t, a*u + e2) leaves the noise -e*u + e1 + e2*sk,
+    /// where u and sk are ternary with variance 2/3 and every error has variance
+    /// `error_dist.variance(sigma)`.
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// assert!((params.fresh_noise_std() - 30.2).abs() < 0.1);
+    /// ```
+    pub fn fresh_noise_std(&self) -> f64 {
+        let var_e = self.error_dist.variance(self.sigma);
+        (2.0 * self.n as f64 * var_e * 2.0 / 3.0 + var_e).sqrt()
+    }
+
+    /// Bits of noise headroom left for a given noise magnitude
+    /// # Arguments:
+    /// * `noise` - largest absolute noise coefficient, or an estimate of it
+    /// # Returns:
+    /// log2(q/(2t)) - log2(noise), clamped at 0 where decryption may fail
+    pub fn headroom_bits(&self, noise: f64) -> f64 {
+        (self.q as f64 / (2.0 * self.t as f64) / noise.max(1.0)).log2().max(0.0)
+    }
+
+    /// Check that another parameter set describes the same ring and moduli
+    /// # Arguments:
+    /// * `other` - parameters attached to a key or ciphertext
+    /// # Returns:
+    /// `Ok(())` if `n`, `q` and `t` agree, otherwise a parameter mismatch error
+    pub fn check_match(&self, other: &Parameters) -> Result<(), RingLweError> {
+        if self.n != other.n {
+            return Err(RingLweError::ParameterMismatch(format!("n: expected {}, found {}", self.n, other.n)));
+        }
+        if self.q != other.q {
+            return Err(RingLweError::ParameterMismatch(format!("q: expected {}, found {}", self.q, other.q)));
+        }
+        if self.t != other.t {
+            return Err(RingLweError::ParameterMismatch(format!("t: expected {}, found {}", self.t, other.t)));
+        }
+        Ok(())
+    }
+
+    /// Parameters for a ciphertext switched down to a modulus of the chain
+    /// # Arguments:
+    /// * `q` - ciphertext modulus, either `self.q` or an entry of `self.modulus_chain`
+    /// # Returns:
+    /// the same parameters with modulus q and the rest of the chain below it, or an error if
+    /// q is not on the chain
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::builder().q(1073479681).modulus_chain(&[786433, 12289]).build().unwrap();
+    /// let lower = params.at_modulus(786433).unwrap();
+    /// assert_eq!((lower.q, lower.modulus_chain), (786433, vec![12289]));
+    /// assert!(params.at_modulus(40961).is_err());
+    /// ```
+    pub fn at_modulus(&self, q: i64) -> Result<Parameters, RingLweError> {
+        if q == self.q {
+            return Ok(self.clone());
+        }
+        let position = self.modulus_chain.iter().position(|&q_i| q_i == q).ok_or_else(|| {
+            RingLweError::InvalidParameters(format!("q = {} is not on the modulus chain {:?} below {}", q, self.modulus_chain, self.q))
+        })?;
+        Parameters::builder()
+            .n(self.n)
+            .q(q)
+            .t(self.t)
+            .sigma(self.sigma)
+            .error_distribution(self.error_dist)
+            .modulus_chain(&self.modulus_chain[position + 1..])
+            .build()
+    }
+
+    /// Check that k is a Galois element of Z_q[X]/(x^n+1), i.e. that x -> x^k is an automorphism
+    /// # Arguments:
+    /// * `k` - exponent of the automorphism
+    /// # Returns:
+    /// `Ok(())` if k is odd and in [1, 2n), otherwise an invalid parameters error
+    pub fn check_galois_element(&self, k: usize) -> Result<(), RingLweError> {
+        if k.is_multiple_of(2) || k >= 2 * self.n {
+            return Err(RingLweError::InvalidParameters(format!("Galois element {} is not odd and in [1, 2n = {})", k, 2 * self.n)));
+        }
+        Ok(())
     }
 }
 
 /// Take remainder of the coefficients of a polynom by a given modulus
 /// # Arguments:
 /// * `x` - polynomial in Z[X]
-///	* `modulus` - coefficient modulus
+/// * `modulus` - coefficient modulus
 /// # Returns:
 /// polynomial in Z_modulus[X]
 pub fn mod_coeffs(x : Polynomial<i64>, modulus : i64) -> Polynomial<i64> {
@@ -45,14 +427,14 @@ pub fn mod_coeffs(x : Polynomial<i64>, modulus : i64) -> Polynomial<i64> {
 	let coeffs = x.coeffs();
 	let mut newcoeffs = vec![];
 	let mut c;
-	if coeffs.len() == 0 {
+	if coeffs.is_empty() {
 		// return original input for the zero polynomial
 		x
 	} else {
-		for i in 0..coeffs.len() {
-			c = coeffs[i].rem_euclid(modulus);
+		for coeff in coeffs {
+			c = coeff.rem_euclid(modulus);
 			if c > modulus/2 {
-				c = c-modulus;
+				c -= modulus;
 			}
 			newcoeffs.push(c);
 		}
@@ -60,20 +442,38 @@ pub fn mod_coeffs(x : Polynomial<i64>, modulus : i64) -> Polynomial<i64> {
 	}
 }
 
+/// Reduce coefficients to centered residues like `mod_coeffs`, using Barrett
+/// reduction when the modulus fits in 32 bits, on 16-bit words when the modulus
+/// and the coefficients allow
+fn reduce_coeffs(x: Polynomial<i64>, modulus: i64) -> Polynomial<i64> {
+	let coeffs = x.coeffs();
+	match (u16::try_from(modulus), u32::try_from(modulus)) {
+		(Ok(q), _) if q >= 2 && !coeffs.is_empty() && coeffs.iter().all(|&c| i32::try_from(c).is_ok()) => {
+			let barrett = Barrett16::new(q);
+			Polynomial::new(coeffs.iter().map(|&c| barrett.reduce_centered(c as i32) as i64).collect())
+		}
+		(_, Ok(q)) if q >= 2 && !coeffs.is_empty() => {
+			let barrett = Barrett32::new(q);
+			Polynomial::new(coeffs.iter().map(|&c| barrett.reduce_centered(c)).collect())
+		}
+		_ => mod_coeffs(x, modulus),
+	}
+}
+
 /// Polynomial emainder of x modulo f assuming f=x^n+1
 /// # Arguments:
 /// * `x` - polynomial in Z[X]
-///	* `f` - polynomial modulus
+/// * `f` - polynomial modulus
 /// # Returns:
 /// polynomial in Z[X]/(f)
 pub fn polyrem(x: Polynomial<i64>, f: &Polynomial<i64>) -> Polynomial<i64> {
 	let n = f.coeffs().len()-1;
 	let mut coeffs = x.coeffs().to_vec();
 	if coeffs.len() < n+1 {
-		return Polynomial::new(coeffs)
+		Polynomial::new(coeffs)
 	} else{
 		for i in n..coeffs.len() {
-			coeffs[i % n] = coeffs[i % n]+(-1 as i64).pow((i/n).try_into().unwrap())*coeffs[i];
+			coeffs[i % n] += (-1_i64).pow((i/n).try_into().unwrap())*coeffs[i];
 		}
 		coeffs.resize(n,0);
 		Polynomial::new(coeffs)
@@ -82,12 +482,12 @@ pub fn polyrem(x: Polynomial<i64>, f: &Polynomial<i64>) -> Polynomial<i64> {
 
 /// Multiply two polynomials
 /// # Arguments:
-///	* `x` - polynomial to be multiplied
+/// * `x` - polynomial to be multiplied
 /// * `y` - polynomial to be multiplied.
 /// * `modulus` - coefficient modulus.
-///	* `f` - polynomial modulus.
+/// * `f` - polynomial modulus.
 /// # Returns:
-///	polynomial in Z_q[X]/(f)
+/// polynomial in Z_q[X]/(f)
 #[allow(dead_code)]
 pub fn polymul(x : &Polynomial<i64>, y : &Polynomial<i64>, q : i64, f : &Polynomial<i64>) -> Polynomial<i64> {
 	let mut r = x*y;
@@ -102,13 +502,13 @@ pub fn polymul(x : &Polynomial<i64>, y : &Polynomial<i64>, q : i64, f : &Polynom
 
 /// Multiply two polynomials using fast NTT algorithm
 /// # Arguments:
-///	* `x` - polynomial to be multiplied
+/// * `x` - polynomial to be multiplied
 /// * `y` - polynomial to be multiplied.
 /// * `q` - coefficient modulus.
-///	* `f` - polynomial modulus.
+/// * `f` - polynomial modulus.
 /// * `omega` - n-th root of unity
 /// # Returns:
-///	polynomial in Z_q[X]/(f)
+/// polynomial in Z_q[X]/(f)
 /// # Example:
 /// ```
 /// let p: i64 = 17; // Prime modulus
@@ -153,20 +553,567 @@ pub fn polymul_fast(
     mod_coeffs(r, q)
 }
 
+/// Modular inverse by the extended Euclidean algorithm
+/// # Arguments:
+/// * `a` - integer to invert
+/// * `modulus` - modulus
+/// # Returns:
+/// a^-1 mod modulus in [0, modulus), or `None` if gcd(a, modulus) != 1
+pub fn mod_inverse(a: i64, modulus: i64) -> Option<i64> {
+    let (mut r0, mut r1) = (a.rem_euclid(modulus), modulus);
+    let (mut s0, mut s1) = (1i64, 0i64);
+    while r1 != 0 {
+        let quotient = r0 / r1;
+        (r0, r1) = (r1, r0 - quotient * r1);
+        (s0, s1) = (s1, s0 - quotient * s1);
+    }
+    if r0 == 1 {
+        Some(s0.rem_euclid(modulus))
+    } else {
+        None
+    }
+}
+
+/// Reverse the lowest `bits` bits of `x`
+fn bit_reverse(x: usize, bits: u32) -> usize {
+    if bits == 0 {
+        0
+    } else {
+        x.reverse_bits() >> (usize::BITS - bits)
+    }
+}
+
+/// Powers base^bitrev(i) mod q for i in 0..n
+fn bit_reversed_powers(base: i64, n: usize, q: i64) -> Vec<i64> {
+    let mut powers = vec![1i64; n];
+    for i in 1..n {
+        powers[i] = powers[i-1] * base % q;
+    }
+    let bits = n.trailing_zeros();
+    (0..n).map(|i| powers[bit_reverse(i, bits)]).collect()
+}
+
+/// Precomputed tables for the negacyclic NTT over Z_q[X]/(x^n+1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NttContext {
+    n: usize,
+    q: i64,
+    psi: i64,
+    reducer: Reducer,      // Montgomery16 and Barrett16 when q < 2^15, the 32-bit ones otherwise
+    psi_rev: Vec<u32>,     // psi^bitrev(i) in Montgomery form, forward twiddles
+    psi_inv_rev: Vec<u32>, // psi^-bitrev(i) in Montgomery form, inverse twiddles
+    n_inv: u32,            // n^-1 mod q in Montgomery form
+}
+
+impl NttContext {
+    /// Precompute the twiddle tables
+    /// # Arguments:
+    /// * `n` - transform length, a power of two
+    /// * `q` - odd coefficient modulus, q < 2^31
+    /// * `psi` - primitive 2n-th root of unity mod q
+    /// # Returns:
+    /// NTT context for Z_q[X]/(x^n+1)
+    pub fn new(n: usize, q: i64, psi: i64) -> Self {
+        let reducer = Reducer::new(u32::try_from(q).expect("q is below 2^31"));
+        let to_mont = |powers: Vec<i64>| -> Vec<u32> {
+            powers.into_iter().map(|p| reducer.montgomery_form(p as u32)).collect()
+        };
+        let psi_inv = mod_inverse(psi, q).expect("psi is a unit mod q");
+        let n_inv = mod_inverse(n as i64, q).expect("n is a unit mod q");
+        NttContext {
+            n,
+            q,
+            psi,
+            reducer,
+            psi_rev: to_mont(bit_reversed_powers(psi, n, q)),
+            psi_inv_rev: to_mont(bit_reversed_powers(psi_inv, n, q)),
+            n_inv: reducer.montgomery_form(n_inv as u32),
+        }
+    }
+
+    /// Precompute the twiddle tables for the ring of a parameter set
+    /// # Arguments:
+    /// * `params` - ring-LWE parameters, with `omega` a primitive 2n-th root of unity mod q
+    pub fn from_params(params: &Parameters) -> Self {
+        NttContext::new(params.n, params.q, params.omega)
+    }
+
+    /// Transform length
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Coefficient modulus
+    pub fn q(&self) -> i64 {
+        self.q
+    }
+
+    /// Primitive 2n-th root of unity the tables are built from
+    pub fn psi(&self) -> i64 {
+        self.psi
+    }
+
+    /// Forward negacyclic NTT in place
+    /// # Arguments:
+    /// * `a` - n coefficients, overwritten by their evaluations at the odd powers of psi
+    ///   in bit-reversed order, with values in [0, q)
+    pub fn forward(&self, a: &mut [i64]) {
+        let (n, q) = (self.n, self.q);
+        for c in a.iter_mut() {
+            *c = c.rem_euclid(q);
+        }
+        // Cooley-Tukey butterflies, with the psi twist merged into the twiddle factors
+        let mut t = n;
+        let mut m = 1;
+        while m < n {
+            t /= 2;
+            for i in 0..m {
+                let j1 = 2 * i * t;
+                let s = self.psi_rev[m + i];
+                for j in j1..j1 + t {
+                    let u = a[j];
+                    let v = self.reducer.mont_mul(a[j + t] as u32, s) as i64;
+                    a[j] = if u + v >= q { u + v - q } else { u + v };
+                    a[j + t] = if u >= v { u - v } else { u - v + q };
+                }
+            }
+            m *= 2;
+        }
+    }
+
+    /// Inverse negacyclic NTT in place
+    /// # Arguments:
+    /// * `a` - n evaluations in bit-reversed order with values in [0, q), overwritten by
+    ///   the coefficients in [0, q)
+    pub fn inverse(&self, a: &mut [i64]) {
+        let (n, q) = (self.n, self.q);
+        // Gentleman-Sande butterflies, undoing the psi twist in the twiddle factors
+        let mut t = 1;
+        let mut m = n;
+        while m > 1 {
+            let h = m / 2;
+            let mut j1 = 0;
+            for i in 0..h {
+                let s = self.psi_inv_rev[h + i];
+                for j in j1..j1 + t {
+                    let u = a[j];
+                    let v = a[j + t];
+                    a[j] = if u + v >= q { u + v - q } else { u + v };
+                    a[j + t] = self.reducer.mont_mul((u - v + q) as u32, s) as i64;
+                }
+                j1 += 2 * t;
+            }
+            t *= 2;
+            m = h;
+        }
+        for c in a.iter_mut() {
+            *c = self.reducer.mont_mul(*c as u32, self.n_inv) as i64;
+        }
+    }
+
+    /// Multiply two polynomials in Z_q[X]/(x^n+1)
+    /// # Arguments:
+    /// * `x` - polynomial to be multiplied, with at most n coefficients
+    /// * `y` - polynomial to be multiplied, with at most n coefficients
+    /// # Returns:
+    /// polynomial in Z_q[X]/(x^n+1) with centered coefficients
+    /// # Example:
+    /// ```
+    /// let params = ring_lwe::utils::Parameters::default();
+    /// let ntt = params.ntt();
+    /// let a = ring_lwe::utils::gen_uniform_poly(params.n, params.q, None);
+    /// let b = ring_lwe::utils::gen_uniform_poly(params.n, params.q, None);
+    /// assert_eq!(ntt.polymul(&a, &b), ring_lwe::utils::polymul(&a, &b, params.q, &params.f));
+    /// ```
+    pub fn polymul(&self, x: &Polynomial<i64>, y: &Polynomial<i64>) -> Polynomial<i64> {
+        let q = self.q;
+        let mut x_ntt = pad_coeffs(x, self.n);
+        let mut y_ntt = pad_coeffs(y, self.n);
+        self.forward(&mut x_ntt);
+        self.forward(&mut y_ntt);
+        // pointwise product of the evaluations, then back to coefficients
+        let mut r: Vec<i64> = x_ntt.iter().zip(&y_ntt)
+            .map(|(&a, &b)| self.reducer.mul_mod(a as u32, b as u32) as i64)
+            .collect();
+        self.inverse(&mut r);
+        mod_coeffs(Polynomial::new(r), q)
+    }
+
+    /// Transform a polynomial into the NTT domain
+    /// # Arguments:
+    /// * `x` - polynomial with at most n coefficients
+    /// # Returns:
+    /// evaluations of x, ready for pointwise arithmetic
+    pub fn to_ntt(&self, x: &Polynomial<i64>) -> NttPoly {
+        let mut evals = pad_coeffs(x, self.n);
+        self.forward(&mut evals);
+        NttPoly { evals: evals.into_iter().map(|c| c as u32).collect(), q: self.q }
+    }
+
+    /// Transform an NTT-domain polynomial back to coefficients
+    /// # Arguments:
+    /// * `x` - evaluations produced by this context
+    /// # Returns:
+    /// polynomial in Z_q[X]/(x^n+1) with centered coefficients
+    pub fn from_ntt(&self, x: &NttPoly) -> Polynomial<i64> {
+        mod_coeffs(Polynomial::new(self.inverse_evals(x)), self.q)
+    }
+
+    /// Inverse transform of NTT-domain evaluations into coefficients in [0, q)
+    fn inverse_evals(&self, x: &NttPoly) -> Vec<i64> {
+        assert_eq!((x.evals.len(), x.q), (self.n, self.q), "NTT polynomial from a different ring");
+        let mut coeffs: Vec<i64> = x.evals.iter().map(|&c| c as i64).collect();
+        self.inverse(&mut coeffs);
+        coeffs
+    }
+}
+
+/// Polynomial in Z_q[X]/(x^n+1) held in the NTT domain
+///
+/// Products and sums are pointwise, so a long-lived operand such as a key
+/// only needs to be transformed once.
+/// # Example:
+/// ```
+/// let params = ring_lwe::utils::Parameters::default();
+/// let ntt = params.ntt();
+/// let a = ring_lwe::utils::gen_uniform_poly(params.n, params.q, None);
+/// let b = ring_lwe::utils::gen_uniform_poly(params.n, params.q, None);
+/// let ab = &ntt.to_ntt(&a) * &ntt.to_ntt(&b);
+/// assert_eq!(ntt.from_ntt(&ab), ntt.polymul(&a, &b));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NttPoly {
+    evals: Vec<u32>, // evaluations in bit-reversed order, in [0, q)
+    q: i64,
+}
+
+impl NttPoly {
+    /// Evaluations in bit-reversed order, with values in [0, q)
+    pub fn evals(&self) -> &[u32] {
+        &self.evals
+    }
+
+    /// Apply a coefficientwise operation to two NTT polynomials over the same ring
+    fn zip_with(&self, other: &NttPoly, op: impl Fn(u32, u32) -> u32) -> NttPoly {
+        assert_eq!((self.evals.len(), self.q), (other.evals.len(), other.q), "NTT polynomials from different rings");
+        let evals = self.evals.iter().zip(&other.evals).map(|(&a, &b)| op(a, b)).collect();
+        NttPoly { evals, q: self.q }
+    }
+}
+
+/// Pointwise product, i.e. multiplication in Z_q[X]/(x^n+1)
+impl Mul for &NttPoly {
+    type Output = NttPoly;
+
+    fn mul(self, other: &NttPoly) -> NttPoly {
+        let reducer = Reducer::new(self.q as u32);
+        self.zip_with(other, |a, b| reducer.mul_mod(a, b))
+    }
+}
+
+/// Pointwise sum, i.e. addition in Z_q[X]/(x^n+1)
+impl Add for &NttPoly {
+    type Output = NttPoly;
+
+    fn add(self, other: &NttPoly) -> NttPoly {
+        let q = self.q as u32;
+        self.zip_with(other, |a, b| if a + b >= q { a + b - q } else { a + b })
+    }
+}
+
+/// Unsigned machine word holding the coefficients of a `RingPoly`
+pub trait CoeffWord: Copy + Default + fmt::Debug + PartialEq + Eq {
+    /// Largest value the word can hold
+    const MAX: u64;
+
+    /// Convert from a u64 that fits in the word
+    fn from_u64(x: u64) -> Self;
+
+    /// Widen to u64
+    fn to_u64(self) -> u64;
+}
+
+macro_rules! impl_coeff_word {
+    ($($word:ty),*) => {$(
+        impl CoeffWord for $word {
+            const MAX: u64 = <$word>::MAX as u64;
+
+            fn from_u64(x: u64) -> Self {
+                x as $word
+            }
+
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+        }
+    )*};
+}
+
+impl_coeff_word!(u16, u32, u64);
+
+/// Polynomial in Z_q[X]/(x^n+1) with exactly n coefficients in [0, q), each stored in a word W
+///
+/// Use u16 for q < 2^16 such as 12289 or 3329, and u32 for anything the NTT supports.
+/// # Example:
+/// ```
+/// use ring_lwe::utils::RingPoly;
+/// let params = ring_lwe::utils::Parameters::default();
+/// let a = ring_lwe::utils::gen_uniform_poly(params.n, params.q, None);
+/// let b = ring_lwe::utils::gen_uniform_poly(params.n, params.q, None);
+/// let (ra, rb) = (RingPoly::<u16>::from_poly(&a, params.n, params.q), RingPoly::<u16>::from_poly(&b, params.n, params.q));
+/// assert_eq!((&ra + &rb).to_poly(), ring_lwe::utils::polyadd(&a, &b, params.q, &params.f));
+/// assert_eq!(ra.mul(&rb, &params.ntt()).to_poly(), ring_lwe::utils::polymul(&a, &b, params.q, &params.f));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingPoly<W: CoeffWord> {
+    coeffs: Vec<W>, // n coefficients in [0, q)
+    q: u64,
+}
+
+impl<W: CoeffWord> RingPoly<W> {
+    /// Zero polynomial
+    /// # Arguments:
+    /// * `n` - number of coefficients
+    /// * `q` - coefficient modulus, with q - 1 fitting in W and q < 2^63
+    pub fn zero(n: usize, q: i64) -> Self {
+        assert!(q >= 2 && (q - 1) as u64 <= W::MAX, "modulus {} does not fit the coefficient word", q);
+        RingPoly { coeffs: vec![W::default(); n], q: q as u64 }
+    }
+
+    /// Reduce a polynomial into Z_q[X]/(x^n+1)
+    /// # Arguments:
+    /// * `x` - polynomial with any number of integer coefficients
+    /// * `n` - degree of the polynomial modulus x^n+1
+    /// * `q` - coefficient modulus, with q - 1 fitting in W
+    pub fn from_poly(x: &Polynomial<i64>, n: usize, q: i64) -> Self {
+        let mut r = RingPoly::zero(n, q);
+        let mut acc = vec![0u64; n];
+        // fold x^i onto (-1)^(i/n) x^(i mod n)
+        for (i, c) in x.coeffs().iter().enumerate() {
+            let c = c.rem_euclid(q) as u64;
+            let c = if (i / n).is_multiple_of(2) { c } else { (r.q - c) % r.q };
+            acc[i % n] = (acc[i % n] + c) % r.q;
+        }
+        r.coeffs = acc.into_iter().map(W::from_u64).collect();
+        r
+    }
+
+    /// Centered polynomial in Z_q[X]/(x^n+1), with coefficients in (-q/2, q/2]
+    pub fn to_poly(&self) -> Polynomial<i64> {
+        Polynomial::new(self.to_centered())
+    }
+
+    /// All n coefficients as centered residues in (-q/2, q/2]
+    pub fn to_centered(&self) -> Vec<i64> {
+        let q = self.q;
+        self.coeffs.iter()
+            .map(|c| {
+                let c = c.to_u64();
+                if c > q / 2 { c as i64 - q as i64 } else { c as i64 }
+            })
+            .collect()
+    }
+
+    /// Coefficients in [0, q)
+    pub fn coeffs(&self) -> &[W] {
+        &self.coeffs
+    }
+
+    /// Number of coefficients n
+    pub fn len(&self) -> usize {
+        self.coeffs.len()
+    }
+
+    /// Whether the polynomial ring is trivial, i.e. n = 0
+    pub fn is_empty(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// Coefficient modulus q
+    pub fn modulus(&self) -> i64 {
+        self.q as i64
+    }
+
+    /// Copy the coefficients into another word type V, which must hold q - 1
+    pub fn to_word<V: CoeffWord>(&self) -> RingPoly<V> {
+        let mut r = RingPoly::zero(self.len(), self.modulus());
+        r.coeffs = self.coeffs.iter().map(|c| V::from_u64(c.to_u64())).collect();
+        r
+    }
+
+    /// Transform into the NTT domain
+    /// # Arguments:
+    /// * `ntt` - NTT context for the same n and q
+    pub fn to_ntt(&self, ntt: &NttContext) -> NttPoly {
+        assert_eq!((self.len(), self.modulus()), (ntt.n(), ntt.q()), "polynomial from a different ring");
+        let mut evals: Vec<i64> = self.coeffs.iter().map(|c| c.to_u64() as i64).collect();
+        ntt.forward(&mut evals);
+        NttPoly { evals: evals.into_iter().map(|c| c as u32).collect(), q: ntt.q() }
+    }
+
+    /// Transform out of the NTT domain
+    /// # Arguments:
+    /// * `x` - evaluations produced by `ntt`
+    /// * `ntt` - NTT context for the ring
+    pub fn from_ntt(x: &NttPoly, ntt: &NttContext) -> Self {
+        let mut r = RingPoly::zero(ntt.n(), ntt.q());
+        r.coeffs = ntt.inverse_evals(x).into_iter().map(|c| W::from_u64(c as u64)).collect();
+        r
+    }
+
+    /// Multiply two polynomials with the negacyclic NTT
+    /// # Arguments:
+    /// * `other` - polynomial over the same ring
+    /// * `ntt` - NTT context for the ring
+    pub fn mul(&self, other: &RingPoly<W>, ntt: &NttContext) -> RingPoly<W> {
+        RingPoly::from_ntt(&(&self.to_ntt(ntt) * &other.to_ntt(ntt)), ntt)
+    }
+
+    /// Multiply by a polynomial already in the NTT domain
+    /// # Arguments:
+    /// * `other` - evaluations produced by `ntt`
+    /// * `ntt` - NTT context for the ring
+    pub fn mul_ntt(&self, other: &NttPoly, ntt: &NttContext) -> RingPoly<W> {
+        RingPoly::from_ntt(&(&self.to_ntt(ntt) * other), ntt)
+    }
+
+    /// Apply the automorphism x -> x^k
+    /// # Arguments:
+    /// * `k` - odd exponent in [1, 2n)
+    /// # Returns:
+    /// p(x^k) mod x^n+1, a signed permutation of the coefficients
+    /// # Example:
+    /// ```
+    /// use polynomial_ring::Polynomial;
+    /// use ring_lwe::utils::RingPoly;
+    /// let p = RingPoly::<u32>::from_poly(&Polynomial::new(vec![1, 2, 3, 4]), 4, 17);
+    /// // x^3 -> x^9 = x, x^2 -> x^6 = -x^2, x -> x^3
+    /// assert_eq!(p.automorphism(3).to_poly(), Polynomial::new(vec![1, 4, -3, 2]));
+    /// ```
+    pub fn automorphism(&self, k: usize) -> RingPoly<W> {
+        let n = self.len();
+        assert!(!k.is_multiple_of(2) && k < 2 * n, "Galois element {} is not odd and in [1, 2n = {})", k, 2 * n);
+        let mut coeffs = vec![W::default(); n];
+        for (i, c) in self.coeffs.iter().enumerate() {
+            // x^(i*k) wraps around to -x^(i*k - n) for exponents in [n, 2n)
+            let j = i * k % (2 * n);
+            let c = c.to_u64();
+            coeffs[j % n] = W::from_u64(if j < n { c } else { (self.q - c) % self.q });
+        }
+        RingPoly { coeffs, q: self.q }
+    }
+
+    /// Apply a coefficientwise operation to two polynomials over the same ring
+    fn zip_with(&self, other: &RingPoly<W>, op: impl Fn(u64, u64) -> u64) -> RingPoly<W> {
+        assert_eq!((self.len(), self.q), (other.len(), other.q), "polynomials from different rings");
+        let coeffs = self.coeffs.iter().zip(&other.coeffs)
+            .map(|(a, b)| W::from_u64(op(a.to_u64(), b.to_u64())))
+            .collect();
+        RingPoly { coeffs, q: self.q }
+    }
+}
+
+/// Coefficientwise sum mod q
+impl<W: CoeffWord> Add for &RingPoly<W> {
+    type Output = RingPoly<W>;
+
+    fn add(self, other: &RingPoly<W>) -> RingPoly<W> {
+        let q = self.q;
+        self.zip_with(other, |a, b| if a + b >= q { a + b - q } else { a + b })
+    }
+}
+
+/// Coefficientwise difference mod q
+impl<W: CoeffWord> Sub for &RingPoly<W> {
+    type Output = RingPoly<W>;
+
+    fn sub(self, other: &RingPoly<W>) -> RingPoly<W> {
+        let q = self.q;
+        self.zip_with(other, |a, b| if a >= b { a - b } else { a + q - b })
+    }
+}
+
+/// Additive inverse mod q
+impl<W: CoeffWord> Neg for &RingPoly<W> {
+    type Output = RingPoly<W>;
+
+    fn neg(self) -> RingPoly<W> {
+        let q = self.q;
+        let coeffs = self.coeffs.iter()
+            .map(|c| W::from_u64(if c.to_u64() == 0 { 0 } else { q - c.to_u64() }))
+            .collect();
+        RingPoly { coeffs, q }
+    }
+}
+
+/// Forward negacyclic NTT over Z_q[X]/(x^n+1)
+/// # Arguments:
+/// * `a` - n coefficients, n a power of two
+/// * `psi` - primitive 2n-th root of unity mod q
+/// * `q` - coefficient modulus, q < 2^31
+/// # Returns:
+/// evaluations of a at the odd powers of psi, in bit-reversed order, with values in [0, q)
+pub fn ntt_negacyclic(a: &[i64], psi: i64, q: i64) -> Vec<i64> {
+    let mut a = a.to_vec();
+    NttContext::new(a.len(), q, psi).forward(&mut a);
+    a
+}
+
+/// Inverse negacyclic NTT over Z_q[X]/(x^n+1)
+/// # Arguments:
+/// * `a` - n evaluations in bit-reversed order with values in [0, q), as returned by `ntt_negacyclic`
+/// * `psi` - primitive 2n-th root of unity mod q
+/// * `q` - coefficient modulus, q < 2^31
+/// # Returns:
+/// n coefficients in [0, q)
+pub fn intt_negacyclic(a: &[i64], psi: i64, q: i64) -> Vec<i64> {
+    let mut a = a.to_vec();
+    NttContext::new(a.len(), q, psi).inverse(&mut a);
+    a
+}
+
+/// Multiply two polynomials in Z_q[X]/(x^n+1) with the negacyclic NTT
+/// # Arguments:
+/// * `x` - polynomial to be multiplied, with at most n coefficients
+/// * `y` - polynomial to be multiplied, with at most n coefficients
+/// * `q` - coefficient modulus, q < 2^31
+/// * `f` - polynomial modulus x^n+1
+/// * `omega` - primitive 2n-th root of unity mod q, as stored in `Parameters`
+/// # Returns:
+/// polynomial in Z_q[X]/(f), computed without zero-padding or a polyrem pass
+/// # Example:
+/// ```
+/// let params = ring_lwe::utils::Parameters::default();
+/// let a = ring_lwe::utils::gen_uniform_poly(params.n, params.q, None);
+/// let b = ring_lwe::utils::gen_uniform_poly(params.n, params.q, None);
+/// let c_std = ring_lwe::utils::polymul(&a, &b, params.q, &params.f);
+/// let c_fast = ring_lwe::utils::polymul_negacyclic(&a, &b, params.q, &params.f, params.omega);
+/// assert_eq!(c_std, c_fast);
+/// ```
+pub fn polymul_negacyclic(
+    x: &Polynomial<i64>,
+    y: &Polynomial<i64>,
+    q: i64,
+    f: &Polynomial<i64>,
+    omega: i64
+) -> Polynomial<i64> {
+    NttContext::new(f.coeffs().len() - 1, q, omega).polymul(x, y)
+}
+
 
 /// Add two polynomials
 /// # Arguments:
-///	* `x` - polynomial to be added
+/// * `x` - polynomial to be added
 /// * `y` - polynomial to be added.
 /// * `modulus` - coefficient modulus.
-///	* `f` - polynomial modulus.
+/// * `f` - polynomial modulus.
 /// # Returns:
-///	polynomial in Z_modulus[X]/(f)
+/// polynomial in Z_modulus[X]/(f)
 pub fn polyadd(x : &Polynomial<i64>, y : &Polynomial<i64>, modulus : i64, f : &Polynomial<i64>) -> Polynomial<i64> {
 	let mut r = x+y;
     r = polyrem(r,f);
     if modulus != 0 {
-        mod_coeffs(r, modulus)
+        reduce_coeffs(r, modulus)
     }
     else{
         r
@@ -175,15 +1122,15 @@ pub fn polyadd(x : &Polynomial<i64>, y : &Polynomial<i64>, modulus : i64, f : &P
 
 /// Additive inverse of a polynomial
 /// # Arguments:
-///	* `x` - polynomial to be inverted
+/// * `x` - polynomial to be inverted
 /// * `modulus` - coefficient modulus.
 /// # Returns:
-///	polynomial in Z_modulus[X]
+/// polynomial in Z_modulus[X]
 pub fn polyinv(x : &Polynomial<i64>, modulus: i64) -> Polynomial<i64> {
     //Additive inverse of polynomial x modulo modulus
     let y = -x;
     if modulus != 0{
-      mod_coeffs(y, modulus)
+      reduce_coeffs(y, modulus)
     }
     else {
       y
@@ -192,103 +1139,432 @@ pub fn polyinv(x : &Polynomial<i64>, modulus: i64) -> Polynomial<i64> {
 
 /// Subtract two polynomials
 /// # Arguments:
-///	* `x` - polynomial to be subtracted
+/// * `x` - polynomial to be subtracted
 /// * `y` - polynomial to be subtracted.
 /// * `modulus` - coefficient modulus.
-///	* `f` - polynomial modulus.
+/// * `f` - polynomial modulus.
 /// # Returns:
-///	polynomial in Z_modulus[X]/(f)
+/// polynomial in Z_modulus[X]/(f)
 #[allow(dead_code)]
 pub fn polysub(x : &Polynomial<i64>, y : &Polynomial<i64>, modulus : i64, f : &Polynomial<i64>) -> Polynomial<i64> {
 	polyadd(x, &polyinv(y, modulus), modulus, f)
 }
 
-/// Generate a binary polynomial
+/// Domains for deriving independent randomness streams from a single seed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedDomain {
+    Secret,                 // secret key polynomial sk
+    Error,                  // keygen error polynomial e
+    Uniform,                // uniform public polynomial a
+    Ephemeral,              // ephemeral encryption polynomial u
+    Error1,                 // encryption error polynomial e1
+    Error2,                 // encryption error polynomial e2
+    Block(u64),             // sub-seed for the i-th block of a multi-block message
+    RelinKey,               // sub-seed for the relinearization key
+    KeySwitchUniform(u64),  // uniform polynomial a_i of the i-th key switching digit
+    KeySwitchError(u64),    // error polynomial e_i of the i-th key switching digit
+    GaloisKey(u64),         // sub-seed for the Galois key of element k
+}
+
+impl SeedDomain {
+    /// Domain separation tag and index for this domain
+    fn tag(&self) -> (u64, u64) {
+        match *self {
+            SeedDomain::Secret => (1, 0),
+            SeedDomain::Error => (2, 0),
+            SeedDomain::Uniform => (3, 0),
+            SeedDomain::Ephemeral => (4, 0),
+            SeedDomain::Error1 => (5, 0),
+            SeedDomain::Error2 => (6, 0),
+            SeedDomain::Block(i) => (7, i),
+            SeedDomain::RelinKey => (8, 0),
+            SeedDomain::KeySwitchUniform(i) => (9, i),
+            SeedDomain::KeySwitchError(i) => (10, i),
+            SeedDomain::GaloisKey(k) => (11, k),
+        }
+    }
+}
+
+/// Derive an independent seed for one sampled polynomial from a master seed
+/// # Arguments:
+/// * `seed` - master random seed
+/// * `domain` - which polynomial the derived seed is used for
+/// # Returns:
+/// derived seed, or `None` if no master seed was given (fresh entropy is then used for every polynomial)
+/// # Example:
+/// ```
+/// use ring_lwe::utils::{expand_seed, SeedDomain};
+/// let sk_seed = expand_seed(Some(42), SeedDomain::Secret);
+/// let e_seed = expand_seed(Some(42), SeedDomain::Error);
+/// assert_ne!(sk_seed, e_seed);
+/// assert_eq!(expand_seed(None, SeedDomain::Secret), None);
+/// ```
+pub fn expand_seed(seed: Option<u64>, domain: SeedDomain) -> Option<u64> {
+    seed.map(|seed| {
+        // key the ChaCha-based StdRng with (label, seed, domain tag, index) and take its first output
+        let (tag, index) = domain.tag();
+        let mut key = [0u8; 32];
+        key[..8].copy_from_slice(b"ring-lwe");
+        key[8..16].copy_from_slice(&seed.to_le_bytes());
+        key[16..24].copy_from_slice(&tag.to_le_bytes());
+        key[24..].copy_from_slice(&index.to_le_bytes());
+        StdRng::from_seed(key).next_u64()
+    })
+}
+
+/// Random number generator for the seed-based convenience functions
 /// # Arguments:
-///	* `size` - number of coefficients
 /// * `seed` - random seed
 /// # Returns:
-///	polynomial in Z_modulus[X]/(f) with coefficients in {0,1}
-#[allow(dead_code)]
-pub fn gen_binary_poly(size : usize, seed: Option<u64>) -> Polynomial<i64> {
-	let between = Uniform::new(0,2);
-    let mut rng = match seed {
+/// `StdRng` seeded from `seed`, or from OS entropy if `seed` is `None`
+pub fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
         Some(seed) => StdRng::seed_from_u64(seed),
         None => StdRng::from_entropy(),
-    };
+    }
+}
+
+/// Source of one independent randomness stream per sampled polynomial
+///
+/// Seeded streams are derived with `expand_seed`; a caller-supplied rng instead seeds
+/// each stream from its own output, in the order the polynomials are sampled.
+pub(crate) enum SeedStreams<'a> {
+    Seed(Option<u64>),
+    Rng(&'a mut dyn RngCore),
+}
+
+impl SeedStreams<'_> {
+    /// Random number generator for the polynomial sampled in `domain`
+    pub(crate) fn stream(&mut self, domain: SeedDomain) -> StdRng {
+        match self {
+            SeedStreams::Seed(seed) => seeded_rng(expand_seed(*seed, domain)),
+            SeedStreams::Rng(rng) => StdRng::from_rng(&mut **rng).expect("random number generator failed"),
+        }
+    }
+
+    /// Streams for a group of polynomials, such as one key switching key, keyed by a sub-seed
+    pub(crate) fn child(&mut self, domain: SeedDomain) -> SeedStreams<'_> {
+        match self {
+            SeedStreams::Seed(seed) => SeedStreams::Seed(expand_seed(*seed, domain)),
+            SeedStreams::Rng(rng) => SeedStreams::Rng(&mut **rng),
+        }
+    }
+}
+
+/// Generate a binary polynomial
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `seed` - random seed
+/// # Returns:
+/// polynomial in Z_modulus[X]/(f) with coefficients in {0,1}
+pub fn gen_binary_poly(size: usize, seed: Option<u64>) -> Polynomial<i64> {
+    gen_binary_poly_with_rng(size, &mut seeded_rng(seed))
+}
+
+/// Generate a binary polynomial from a caller-supplied random number generator
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// polynomial in Z_modulus[X]/(f) with coefficients in {0,1}
+pub fn gen_binary_poly_with_rng<R: RngCore + CryptoRng>(size: usize, rng: &mut R) -> Polynomial<i64> {
+    let between = Uniform::new(0,2);
     let mut coeffs = vec![0i64;size];
-	for i in 0..size {
-		coeffs[i] = between.sample(&mut rng);
-	}
-	Polynomial::new(coeffs)
+    for coeff in coeffs.iter_mut() {
+        *coeff = between.sample(rng);
+    }
+    Polynomial::new(coeffs)
 }
 
 /// Generate a ternary polynomial
 /// # Arguments:
-///	* `size` - number of coefficients
+/// * `size` - number of coefficients
 /// * `seed` - random seed
 /// # Returns:
-///	ternary polynomial with coefficients in {-1,0,+1}
-pub fn gen_ternary_poly(size : usize, seed: Option<u64>) -> Polynomial<i64> {
-	let between = Uniform::new(-1,2);
-    let mut rng = match seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_entropy(),
-    };
+/// ternary polynomial with coefficients in {-1,0,+1}
+pub fn gen_ternary_poly(size: usize, seed: Option<u64>) -> Polynomial<i64> {
+    gen_ternary_poly_with_rng(size, &mut seeded_rng(seed))
+}
+
+/// Generate a ternary polynomial from a caller-supplied random number generator
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// ternary polynomial with coefficients in {-1,0,+1}
+pub fn gen_ternary_poly_with_rng<R: RngCore + CryptoRng>(size: usize, rng: &mut R) -> Polynomial<i64> {
+    let between = Uniform::new(-1,2);
     let mut coeffs = vec![0i64;size];
-	for i in 0..size {
-		coeffs[i] = between.sample(&mut rng);
-	}
-	Polynomial::new(coeffs)
+    for coeff in coeffs.iter_mut() {
+        *coeff = between.sample(rng);
+    }
+    Polynomial::new(coeffs)
+}
+
+/// Generate a centered binomial polynomial
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `eta` - distribution parameter
+/// * `seed` - random seed
+/// # Returns:
+/// polynomial with coefficients sum(a_i - b_i) for 2*eta uniform random bits, in [-eta, eta]
+/// # Example:
+/// ```
+/// let e = ring_lwe::utils::gen_cbd_poly(1024, 8, Some(1));
+/// assert!(e.coeffs().iter().all(|c| c.abs() <= 8));
+/// ```
+pub fn gen_cbd_poly(size: usize, eta: u32, seed: Option<u64>) -> Polynomial<i64> {
+    gen_cbd_poly_with_rng(size, eta, &mut seeded_rng(seed))
+}
+
+/// Generate a centered binomial polynomial from a caller-supplied random number generator
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `eta` - distribution parameter
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// polynomial with coefficients sum(a_i - b_i) for 2*eta uniform random bits, in [-eta, eta]
+pub fn gen_cbd_poly_with_rng<R: RngCore + CryptoRng>(size: usize, eta: u32, rng: &mut R) -> Polynomial<i64> {
+    // each coefficient consumes 2*eta bits of a random byte stream
+    let eta = eta as usize;
+    let mut bytes = vec![0u8; (2 * eta * size).div_ceil(8)];
+    rng.fill_bytes(&mut bytes);
+    let bit = |i: usize| ((bytes[i / 8] >> (i % 8)) & 1) as i64;
+    let mut coeffs = vec![0i64;size];
+    for (i, coeff) in coeffs.iter_mut().enumerate() {
+        let offset = 2 * eta * i;
+        let a: i64 = (offset..offset + eta).map(bit).sum();
+        let b: i64 = (offset + eta..offset + 2 * eta).map(bit).sum();
+        *coeff = a - b;
+    }
+    Polynomial::new(coeffs)
 }
 
 /// Generate a uniform polynomial
 /// # Arguments:
-///	* `size` - number of coefficients
+/// * `size` - number of coefficients
 /// * `q` - coefficient modulus
 /// * `seed` - random seed
 /// # Returns:
-///	uniform polynomial with coefficients in {0,1,...,q-1}
+/// uniform polynomial with coefficients in {0,1,...,q-1}
 pub fn gen_uniform_poly(size: usize, q: i64, seed: Option<u64>) -> Polynomial<i64> {
-	let between = Uniform::new(0,q);
-    let mut rng = match seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_entropy(),
-    };
+    gen_uniform_poly_with_rng(size, q, &mut seeded_rng(seed))
+}
+
+/// Generate a uniform polynomial from a caller-supplied random number generator
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `q` - coefficient modulus
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// uniform polynomial with coefficients in {0,1,...,q-1}
+pub fn gen_uniform_poly_with_rng<R: RngCore + CryptoRng>(size: usize, q: i64, rng: &mut R) -> Polynomial<i64> {
+    let between = Uniform::new(0,q);
     let mut coeffs = vec![0i64;size];
-	for i in 0..size {
-		coeffs[i] = between.sample(&mut rng);
-	}
-	mod_coeffs(Polynomial::new(coeffs),q)
+    for coeff in coeffs.iter_mut() {
+        *coeff = between.sample(rng);
+    }
+    mod_coeffs(Polynomial::new(coeffs),q)
 }
 
 /// Generate a normal polynomial
 /// # Arguments:
-///	* `size` - number of coefficients
+/// * `size` - number of coefficients
 /// * `sigma` - standard deviation
 /// * `seed` - random seed
 /// # Returns:
-///	polynomial with coefficients sampled from a normal distribution
-#[allow(dead_code)]
+/// polynomial with coefficients sampled from a normal distribution
 pub fn gen_normal_poly(size: usize, sigma: f64, seed: Option<u64>) -> Polynomial<i64> {
-	let normal = Normal::new(0.0 as f64, sigma).unwrap();
-    let mut rng = match seed {
-        Some(seed) => StdRng::seed_from_u64(seed),
-        None => StdRng::from_entropy(),
-    };
+    gen_normal_poly_with_rng(size, sigma, &mut seeded_rng(seed))
+}
+
+/// Generate a normal polynomial from a caller-supplied random number generator
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `sigma` - standard deviation
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// polynomial with coefficients sampled from a normal distribution
+pub fn gen_normal_poly_with_rng<R: RngCore + CryptoRng>(size: usize, sigma: f64, rng: &mut R) -> Polynomial<i64> {
+    let normal = Normal::new(0.0_f64, sigma).unwrap();
     let mut coeffs = vec![0i64;size];
-	for i in 0..size {
-		coeffs[i] = normal.sample(&mut rng).round() as i64;
-	}
-	Polynomial::new(coeffs)
+    for coeff in coeffs.iter_mut() {
+        *coeff = normal.sample(rng).round() as i64;
+    }
+    Polynomial::new(coeffs)
+}
+
+/// Fractional bits of the fixed-point Gaussian weights and of the cumulative table
+const CDT_PRECISION: u32 = 63;
+
+/// Bound on sigma that keeps the fixed-point Taylor series of the CDT within i128
+const DISCRETE_GAUSSIAN_MAX_SIGMA: f64 = 32768.0;
+
+/// Bound on the tail cut, far beyond any useful value, that keeps the CDT to about a million entries
+const DISCRETE_GAUSSIAN_MAX_TAIL_CUT: f64 = 20.0;
+
+/// floor(2^63 * exp(-num/den)), up to a few units in the last place, using integer arithmetic only
+fn exp_neg_fixed(num: u128, den: u128) -> u128 {
+    let one: i128 = 1 << CDT_PRECISION;
+    // e^(-fnum/fden) for fnum <= fden by its alternating Taylor series
+    let taylor = |fnum: i128, fden: i128| -> u128 {
+        let (mut term, mut sum, mut j) = (one, one, 1i128);
+        while term != 0 {
+            term = -term * fnum / (fden * j);
+            sum += term;
+            j += 1;
+        }
+        sum as u128
+    };
+    // split num/den = k + f with integer k and f in [0, 1), then e^(-k-f) = e^(-1)^k * e^(-f)
+    let mut result = taylor((num % den) as i128, den as i128);
+    let e_inv = taylor(1, 1);
+    for _ in 0..num / den {
+        if result == 0 {
+            break;
+        }
+        result = (result * e_inv) >> CDT_PRECISION;
+    }
+    result
+}
+
+/// Exact discrete Gaussian sampler over [-bound, bound] using a cumulative distribution table
+///
+/// The table is built and searched with integer arithmetic only, so samples do not depend on
+/// platform floating point. `sigma` and `tail_cut` are only converted to fixed point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscreteGaussianSampler {
+    sigma: f64,
+    tail_cut: f64,
+    bound: i64,
+    cdt: Vec<u64>, // cdt[i] = 2^63 * P(X <= i - bound)
+}
+
+impl DiscreteGaussianSampler {
+    /// Build the table for the discrete Gaussian with parameter sigma
+    /// # Arguments:
+    /// * `sigma` - standard deviation, 0 < sigma < 2^15
+    /// * `tail_cut` - samples are bounded by ceil(tail_cut * sigma) in absolute value, 0 < tail_cut <= 20
+    /// # Returns:
+    /// sampler with P(X = x) proportional to exp(-x^2 / (2 sigma^2)) for |x| <= bound
+    pub fn new(sigma: f64, tail_cut: f64) -> Self {
+        assert!(sigma > 0.0 && sigma < DISCRETE_GAUSSIAN_MAX_SIGMA, "sigma = {} is not in (0, 2^15)", sigma);
+        assert!(tail_cut > 0.0 && tail_cut <= DISCRETE_GAUSSIAN_MAX_TAIL_CUT, "tail cut {} is not in (0, 20]", tail_cut);
+        let bound = (tail_cut * sigma).ceil() as i64;
+        // x^2 / (2 sigma^2) = x^2 * 2^31 / s^2 with sigma = s / 2^16
+        let s = (sigma * 65536.0).round() as u128;
+        let weights: Vec<u128> = (-bound..=bound)
+            .map(|x| exp_neg_fixed(((x * x) as u128) << 31, s * s) >> 16)
+            .collect();
+        let total: u128 = weights.iter().sum();
+        let mut cumulative = 0u128;
+        let cdt = weights
+            .iter()
+            .map(|w| {
+                cumulative += w;
+                ((cumulative << CDT_PRECISION) / total) as u64
+            })
+            .collect();
+        DiscreteGaussianSampler { sigma, tail_cut, bound, cdt }
+    }
+
+    /// Largest absolute value that can be sampled
+    pub fn bound(&self) -> i64 {
+        self.bound
+    }
+
+    /// Draw one sample
+    /// # Arguments:
+    /// * `rng` - random number generator
+    /// # Returns:
+    /// integer in [-bound, bound]
+    pub fn sample<R: RngCore>(&self, rng: &mut R) -> i64 {
+        let r = rng.next_u64() >> (64 - CDT_PRECISION);
+        self.cdt.partition_point(|&c| c <= r) as i64 - self.bound
+    }
+
+    /// Draw a polynomial with independent coefficients
+    /// # Arguments:
+    /// * `size` - number of coefficients
+    /// * `rng` - random number generator
+    pub fn sample_poly<R: RngCore>(&self, size: usize, rng: &mut R) -> Polynomial<i64> {
+        Polynomial::new((0..size).map(|_| self.sample(rng)).collect())
+    }
+}
+
+/// Generate a discrete Gaussian polynomial
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `sigma` - standard deviation
+/// * `tail_cut` - coefficients are bounded by ceil(tail_cut * sigma) in absolute value
+/// * `seed` - random seed
+/// # Returns:
+/// polynomial with coefficients sampled from a discrete Gaussian distribution
+/// # Example:
+/// ```
+/// let e = ring_lwe::utils::gen_discrete_gaussian_poly(1024, 3.2, 6.0, Some(1));
+/// assert!(e.coeffs().iter().all(|c| c.abs() <= 20));
+/// ```
+pub fn gen_discrete_gaussian_poly(size: usize, sigma: f64, tail_cut: f64, seed: Option<u64>) -> Polynomial<i64> {
+    gen_discrete_gaussian_poly_with_rng(size, sigma, tail_cut, &mut seeded_rng(seed))
+}
+
+/// Generate a discrete Gaussian polynomial from a caller-supplied random number generator
+/// # Arguments:
+/// * `size` - number of coefficients
+/// * `sigma` - standard deviation
+/// * `tail_cut` - coefficients are bounded by ceil(tail_cut * sigma) in absolute value
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// polynomial with coefficients sampled from a discrete Gaussian distribution
+pub fn gen_discrete_gaussian_poly_with_rng<R: RngCore + CryptoRng>(size: usize, sigma: f64, tail_cut: f64, rng: &mut R) -> Polynomial<i64> {
+    DiscreteGaussianSampler::new(sigma, tail_cut).sample_poly(size, rng)
+}
+
+/// Generate an error polynomial from the distribution selected in the parameters
+/// # Arguments:
+/// * `params` - ring-LWE parameters
+/// * `seed` - random seed
+/// # Returns:
+/// polynomial with n coefficients sampled from `params.error_dist`
+/// # Example:
+/// ```
+/// use ring_lwe::utils::{Parameters, ErrorDistribution, gen_error_poly};
+/// let params = Parameters::builder()
+///     .error_distribution(ErrorDistribution::CenteredBinomial { eta: 8 })
+///     .build()
+///     .unwrap();
+/// let e = gen_error_poly(&params, Some(1));
+/// assert!(e.coeffs().iter().all(|c| c.abs() <= 8));
+/// ```
+pub fn gen_error_poly(params: &Parameters, seed: Option<u64>) -> Polynomial<i64> {
+    gen_error_poly_with_rng(params, &mut seeded_rng(seed))
+}
+
+/// Generate an error polynomial from a caller-supplied random number generator
+/// # Arguments:
+/// * `params` - ring-LWE parameters
+/// * `rng` - cryptographically secure random number generator
+/// # Returns:
+/// polynomial with n coefficients sampled from `params.error_dist`
+pub fn gen_error_poly_with_rng<R: RngCore + CryptoRng>(params: &Parameters, rng: &mut R) -> Polynomial<i64> {
+    let (n, sigma) = (params.n, params.sigma);
+    match params.error_dist {
+        ErrorDistribution::Ternary => gen_ternary_poly_with_rng(n, rng),
+        ErrorDistribution::RoundedGaussian => gen_normal_poly_with_rng(n, sigma, rng),
+        ErrorDistribution::DiscreteGaussian { .. } => {
+            params.discrete_gaussian().expect("discrete Gaussian error distribution").sample_poly(n, rng)
+        }
+        ErrorDistribution::CenteredBinomial { eta } => gen_cbd_poly_with_rng(n, eta, rng),
+    }
 }
 
 /// nearest integer to the ratio a/b
 /// # Arguments:
-///	* `a` - numerator
+/// * `a` - numerator
 /// * `b` - denominator
 /// # Returns:
-///	nearest integer to the ratio a/b
+/// nearest integer to the ratio a/b
 pub fn nearest_int(a: i64, b: i64) -> i64 {
     if a > 0 {
 		(a + b / 2) / b
@@ -302,17 +1578,178 @@ pub fn nearest_int(a: i64, b: i64) -> i64 {
 /// * `data` - vector of i64
 /// # Returns
 /// * `encoded` - base64 encoded string
-pub fn compress(data: &Vec<i64>) -> String {
-    let serialized_data = bincode::serialize(data).expect("Failed to serialize data");
-    general_purpose::STANDARD.encode(&serialized_data)
+pub fn compress(data: &[i64]) -> String {
+    general_purpose::STANDARD.encode(serialize_coeffs(data))
 }
 
 /// decode and deserialize a base64 encoded string to a vector of i64
 /// # Arguments
 /// * `base64_str` - base64 encoded string
 /// # Returns
-/// * `decoded_data` - vector of i64
-pub fn decompress(base64_str: &str) -> Vec<i64> {
-    let decoded_bytes = general_purpose::STANDARD.decode(base64_str).expect("Failed to decode base64 string");
-    bincode::deserialize(&decoded_bytes).expect("Failed to deserialize data")
-}
\ No newline at end of file
+/// * `decoded_data` - vector of i64, or a decode error for malformed input
+pub fn decompress(base64_str: &str) -> Result<Vec<i64>, RingLweError> {
+    let decoded_bytes = general_purpose::STANDARD.decode(base64_str)?;
+    deserialize_coeffs(&decoded_bytes)
+}
+
+/// bincode-serialize a vector of i64
+/// # Arguments
+/// * `data` - coefficients
+/// # Returns
+/// * `bytes` - length prefix followed by 8 bytes per coefficient
+pub fn serialize_coeffs(data: &[i64]) -> Vec<u8> {
+    bincode::serialize(data).expect("Failed to serialize data")
+}
+
+/// deserialize a bincode-encoded vector of i64
+/// # Arguments
+/// * `bytes` - bytes written by `serialize_coeffs`
+/// # Returns
+/// * `data` - coefficients, or a decode error for malformed input
+pub fn deserialize_coeffs(bytes: &[u8]) -> Result<Vec<i64>, RingLweError> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+/// coefficients of a polynomial zero-padded to a fixed length
+/// # Arguments
+/// * `x` - polynomial with at most `n` coefficients
+/// * `n` - number of coefficients
+/// # Returns
+/// * `coeffs` - vector of exactly `n` coefficients
+pub fn pad_coeffs(x: &Polynomial<i64>, n: usize) -> Vec<i64> {
+    let mut coeffs = x.coeffs().to_vec();
+    coeffs.resize(n, 0);
+    coeffs
+}
+
+/// check that coefficients are centered representatives modulo q
+/// # Arguments
+/// * `coeffs` - coefficients to check
+/// * `q` - coefficient modulus
+/// # Returns
+/// * `Ok(())` if every coefficient lies in [-q/2, q/2], otherwise the first offending coefficient
+pub fn check_coeffs(coeffs: &[i64], q: i64) -> Result<(), RingLweError> {
+    match coeffs.iter().position(|c| c.abs() > q / 2) {
+        Some(index) => Err(RingLweError::InvalidCoefficient { index, value: coeffs[index], modulus: q }),
+        None => Ok(()),
+    }
+}
+/// Wire format of the base64 strings produced by the `*_string` functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WireFormat {
+    /// bincode-serialized i64 coefficients, 8 bytes each plus a length prefix
+    #[default]
+    Bincode,
+    /// coefficients bit-packed in ceil(log2 q) bits each, and ternary secrets in 2 bits
+    Packed,
+}
+
+/// number of bits needed for a residue modulo q
+/// # Arguments
+/// * `q` - modulus, at least 2
+/// # Returns
+/// * `bits` - ceil(log2 q)
+pub fn coeff_bits(q: i64) -> u32 {
+    64 - (q - 1).leading_zeros()
+}
+
+/// bit-pack centered coefficients, least significant bit first
+/// # Arguments
+/// * `coeffs` - coefficients in [-q/2, q/2]
+/// * `q` - coefficient modulus
+/// * `bits` - bits per coefficient, at least `coeff_bits(q)`
+/// # Returns
+/// * `bytes` - ceil(len * bits / 8) bytes holding each coefficient mod q
+/// # Example
+/// ```
+/// use ring_lwe::utils::{pack_coeffs, unpack_coeffs};
+/// let packed = pack_coeffs(&[1, -1, 6144, -6144], 12289, 14);
+/// assert_eq!(packed.len(), 7);
+/// assert_eq!(unpack_coeffs(&packed, 4, 12289, 14).unwrap(), vec![1, -1, 6144, -6144]);
+/// ```
+pub fn pack_coeffs(coeffs: &[i64], q: i64, bits: u32) -> Vec<u8> {
+    let mut bytes = vec![0u8; (coeffs.len() * bits as usize).div_ceil(8)];
+    let mut pos = 0;
+    for c in coeffs {
+        let mut value = c.rem_euclid(q) as u64;
+        let mut remaining = bits as usize;
+        while remaining > 0 {
+            let (byte, offset) = (pos / 8, pos % 8);
+            let take = remaining.min(8 - offset);
+            bytes[byte] |= ((value & ((1 << take) - 1)) << offset) as u8;
+            value >>= take;
+            pos += take;
+            remaining -= take;
+        }
+    }
+    bytes
+}
+
+/// unpack coefficients written by `pack_coeffs`
+/// # Arguments
+/// * `bytes` - packed coefficients
+/// * `count` - number of coefficients
+/// * `q` - coefficient modulus
+/// * `bits` - bits per coefficient used when packing
+/// # Returns
+/// * `coeffs` - centered coefficients, or an error if the length is wrong or a value is not below q
+pub fn unpack_coeffs(bytes: &[u8], count: usize, q: i64, bits: u32) -> Result<Vec<i64>, RingLweError> {
+    let expected = (count * bits as usize).div_ceil(8);
+    if bytes.len() != expected {
+        return Err(RingLweError::LengthMismatch { expected, found: bytes.len() });
+    }
+    let mut coeffs = Vec::with_capacity(count);
+    let mut pos = 0;
+    for index in 0..count {
+        let mut value = 0u64;
+        let mut read = 0;
+        while read < bits as usize {
+            let (byte, offset) = (pos / 8, pos % 8);
+            let take = (bits as usize - read).min(8 - offset);
+            value |= ((bytes[byte] as u64 >> offset) & ((1 << take) - 1)) << read;
+            pos += take;
+            read += take;
+        }
+        let value = value as i64;
+        if value >= q {
+            return Err(RingLweError::InvalidCoefficient { index, value, modulus: q });
+        }
+        coeffs.push(if value > q / 2 { value - q } else { value });
+    }
+    Ok(coeffs)
+}
+
+/// base64 encode raw bytes
+pub fn encode_bytes(bytes: &[u8]) -> String {
+    general_purpose::STANDARD.encode(bytes)
+}
+
+/// decode a base64 string into raw bytes
+pub fn decode_bytes(base64_str: &str) -> Result<Vec<u8>, RingLweError> {
+    Ok(general_purpose::STANDARD.decode(base64_str)?)
+}
+
+/// round a coefficient modulo q to d bits, Compress_d(x) = round(2^d * x / q) mod 2^d
+/// # Arguments
+/// * `x` - coefficient, reduced modulo q
+/// * `q` - coefficient modulus
+/// * `d` - number of bits kept, 1 <= d <= 32
+/// # Returns
+/// * `y` - compressed value in [0, 2^d)
+pub fn compress_d(x: i64, q: i64, d: u32) -> u32 {
+    let x = x.rem_euclid(q) as u64;
+    let q = q as u64;
+    ((((x << d) + q / 2) / q) % (1u64 << d)) as u32
+}
+
+/// lift a d-bit value back to a coefficient, Decompress_d(y) = round(q * y / 2^d)
+/// # Arguments
+/// * `y` - compressed value in [0, 2^d)
+/// * `q` - coefficient modulus
+/// * `d` - number of bits kept
+/// # Returns
+/// * `x` - centered coefficient within about q / 2^(d+1) of the original
+pub fn decompress_d(y: u32, q: i64, d: u32) -> i64 {
+    let x = ((y as u64 * q as u64 + (1u64 << (d - 1))) >> d) as i64;
+    if x > q / 2 { x - q } else { x }
+}